@@ -0,0 +1,71 @@
+#!/usr/bin/env python3
+"""Regenerates src/analysis/english_quadgrams.txt.
+
+Counts letter quadgrams in the prose paragraphs of the Rust books shipped with
+the toolchain documentation (`rustup component add rust-docs`). The books are
+licensed under Apache-2.0 OR MIT, see COPYRIGHT.html next to them.
+
+Usage:
+    python3 scripts/english_quadgrams.py "$(rustc --print sysroot)/share/doc/rust/html" \\
+        > src/analysis/english_quadgrams.txt
+"""
+
+import collections
+import html
+import os
+import re
+import sys
+
+BOOKS = [
+    "book",
+    "nomicon",
+    "reference",
+    "edition-guide",
+    "cargo",
+    "rustc",
+    "rust-by-example",
+]
+
+# Shorter paragraphs are mostly headings, captions and code fragments
+MIN_PARAGRAPH = 80
+
+
+def paragraphs(root):
+    seen = set()
+    for book in BOOKS:
+        for dirpath, dirnames, filenames in os.walk(os.path.join(root, book)):
+            dirnames.sort()
+            for name in sorted(filenames):
+                if not name.endswith(".html") or name == "print.html":
+                    continue
+                with open(os.path.join(dirpath, name), encoding="utf-8", errors="ignore") as f:
+                    page = f.read()
+                for p in re.findall(r"<p>(.*?)</p>", page, re.S):
+                    p = re.sub(r"<code[^>]*>.*?</code>", " ", p, flags=re.S)
+                    p = " ".join(html.unescape(re.sub(r"<[^>]+>", "", p)).split())
+                    if len(p) >= MIN_PARAGRAPH and p not in seen:
+                        seen.add(p)
+                        yield p
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(__doc__)
+    root = sys.argv[1]
+    with open(os.path.join(root, "version_info.html"), encoding="utf-8") as f:
+        info = f.read()
+    version = re.search(r"Rust</a> ([^<]+)</span>", info).group(1)
+    commit = re.search(r'class="hash[^"]*">(\w+)<', info).group(1)
+
+    text = "\n".join(paragraphs(root)).upper()
+    letters = "".join(c for c in text if "A" <= c <= "Z")
+    counts = collections.Counter(letters[i : i + 4] for i in range(len(letters) - 3))
+
+    print("# Quadgram counts of the Rust books (Apache-2.0 OR MIT) in the documentation")
+    print(f"# of Rust {version} ({commit}), generated by scripts/english_quadgrams.py")
+    for quad, count in sorted(counts.items(), key=lambda kv: (-kv[1], kv[0])):
+        print(quad, count)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,112 @@
+use num_integer::Integer;
+
+use crate::util::ModuloU8;
+
+use super::{chi_squared, letter_counts, letters, Language};
+
+const A_UPPER: u8 = b'A';
+const A_LOWER: u8 = b'a';
+const Z_LOWER: u8 = b'z';
+
+const MODULO: u8 = 26;
+
+/// Length of [`AffineCandidate::preview`].
+const PREVIEW_LENGTH: usize = 40;
+
+type Mod = ModuloU8<MODULO>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AffineCandidate {
+    pub m: u8,
+    pub n: u8,
+    /// Chi-squared statistic of decrypted text, lower is more probable.
+    pub score: f64,
+    /// Start of decrypted text (uppercase letters).
+    pub preview: Vec<u8>,
+}
+
+/// Every valid key `(m, n)`, i.e. `m` is invertible modulo 26.
+pub fn keys() -> impl Iterator<Item = (u8, u8)> {
+    (1..MODULO)
+        .filter(|&m| Mod::from(m).inverse().is_ok())
+        .flat_map(|m| (0..MODULO).map(move |n| (m, n)))
+}
+
+/// Decrypts every key and ranks them by chi-squared test against a language.
+pub fn brute_force(text: &[u8], lang: Language) -> Vec<AffineCandidate> {
+    let text = letters(text);
+    let counts = letter_counts(text.iter().copied());
+
+    let mut ret: Vec<_> = keys()
+        .map(|(m, n)| {
+            let m_inv = Mod::from(m).inverse().unwrap();
+            let decrypt = |c: u8| u8::from((Mod::from(c) - Mod::from(n)) * m_inv);
+
+            let mut plain = [0; 26];
+            for (c, &v) in counts.iter().enumerate() {
+                plain[decrypt(c as _) as usize] = v;
+            }
+
+            AffineCandidate {
+                m,
+                n,
+                score: chi_squared(&plain, lang),
+                preview: text
+                    .iter()
+                    .take(PREVIEW_LENGTH)
+                    .map(|&c| decrypt(c) + A_UPPER)
+                    .collect(),
+            }
+        })
+        .collect();
+
+    ret.sort_by(|a, b| a.score.total_cmp(&b.score));
+    ret
+}
+
+fn letter_index(b: u8) -> Result<u8, String> {
+    match b.to_ascii_lowercase() {
+        v @ A_LOWER..=Z_LOWER => Ok(v - A_LOWER),
+        v => Err(format!("{:?} is not a letter", v as char)),
+    }
+}
+
+/// Solves key from two known plaintext and ciphertext letter pairs.
+///
+/// Returns every valid key consistent with both pairs, there can be more than one
+/// if difference of the plaintext letters is not coprime to 26.
+pub fn known_plaintext(plain: [u8; 2], cipher: [u8; 2]) -> Result<Vec<(u8, u8)>, String> {
+    let [p1, p2] = [letter_index(plain[0])?, letter_index(plain[1])?];
+    let [c1, c2] = [letter_index(cipher[0])?, letter_index(cipher[1])?];
+
+    // c1 - c2 = m * (p1 - p2) (mod 26)
+    let d = u8::from(Mod::from(p1) - Mod::from(p2));
+    let e = u8::from(Mod::from(c1) - Mod::from(c2));
+    let g = d.gcd(&MODULO);
+    if e % g != 0 {
+        return Err(format!(
+            "no key maps {} to {}",
+            String::from_utf8_lossy(&plain),
+            String::from_utf8_lossy(&cipher),
+        ));
+    }
+
+    // Solution modulo 26 / g, lifted into g solutions modulo 26
+    let step = (MODULO / g) as i32;
+    let r = ((d / g) as i32).extended_gcd(&step);
+    let m0 = ((e / g) as i32 * r.x).rem_euclid(step);
+
+    let ret: Vec<_> = (0..g as i32)
+        .map(|k| (m0 + k * step) as u8)
+        .filter(|&m| Mod::from(m).inverse().is_ok())
+        .map(|m| (m, u8::from(Mod::from(c1) - Mod::from(m) * Mod::from(p1))))
+        .collect();
+
+    if ret.is_empty() {
+        return Err(format!(
+            "every solution of m is not coprime to {} (GCD of letter difference: {})",
+            MODULO, g
+        ));
+    }
+    Ok(ret)
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use super::{index_of_coincidence, letters};
+
+const A_UPPER: u8 = b'A';
+
+/// Symbols being counted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbols {
+    /// Latin letters (case insensitive), other bytes are ignored.
+    #[default]
+    Letters,
+    /// Every byte.
+    Bytes,
+}
+
+impl Symbols {
+    /// Number of distinct symbols.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Letters => 26,
+            Self::Bytes => 256,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frequency {
+    pub symbols: Symbols,
+    /// Number of counted symbols.
+    pub total: usize,
+    /// Monogram counts, indexed by symbol (A = 0 for letters).
+    pub monograms: Vec<usize>,
+    /// Most common bigrams, sorted descending.
+    pub bigrams: Vec<(Vec<u8>, usize)>,
+    /// Most common trigrams, sorted descending.
+    pub trigrams: Vec<(Vec<u8>, usize)>,
+    /// Index of coincidence.
+    pub ioc: f64,
+    /// Shannon entropy, in bits per symbol.
+    pub entropy: f64,
+}
+
+impl Frequency {
+    /// Counts symbols of text, keeping `top` most common bigrams and trigrams.
+    pub fn new(text: &[u8], symbols: Symbols, top: usize) -> Self {
+        let data = match symbols {
+            Symbols::Letters => letters(text).into_iter().map(|b| b + A_UPPER).collect(),
+            Symbols::Bytes => text.to_vec(),
+        };
+
+        let mut monograms = vec![0; symbols.size()];
+        for &b in &data {
+            let i = match symbols {
+                Symbols::Letters => b - A_UPPER,
+                Symbols::Bytes => b,
+            };
+            monograms[i as usize] += 1;
+        }
+
+        let total = data.len();
+        let entropy = monograms
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let p = c as f64 / total as f64;
+                -p * p.log2()
+            })
+            .sum();
+
+        Self {
+            symbols,
+            total,
+            ioc: index_of_coincidence(&monograms),
+            entropy,
+            monograms,
+            bigrams: ngrams(&data, 2, top),
+            trigrams: ngrams(&data, 3, top),
+        }
+    }
+
+    /// Relative frequency (0 to 1) of a symbol.
+    pub fn relative(&self, symbol: usize) -> f64 {
+        if self.total == 0 {
+            0.
+        } else {
+            self.monograms[symbol] as f64 / self.total as f64
+        }
+    }
+}
+
+fn ngrams(data: &[u8], n: usize, top: usize) -> Vec<(Vec<u8>, usize)> {
+    let mut counts = HashMap::<&[u8], usize>::new();
+    for w in data.windows(n) {
+        *counts.entry(w).or_default() += 1;
+    }
+
+    let mut ret: Vec<_> = counts.into_iter().map(|(k, v)| (k.to_vec(), v)).collect();
+    ret.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ret.truncate(top);
+    ret
+}
@@ -0,0 +1,118 @@
+use crate::util::{MatrixU8, ModuloU8};
+
+use super::letters;
+
+const A_UPPER: u8 = b'A';
+
+const MODULO: u8 = 26;
+
+/// Maximum number of block selections tried before giving up.
+const MAX_SELECTIONS: usize = 100_000;
+
+type Mod = ModuloU8<MODULO>;
+
+/// Matrix with given blocks as columns.
+fn columns(blocks: &[&[u8]]) -> MatrixU8<MODULO> {
+    let n = blocks.len();
+    MatrixU8::new(
+        n,
+        (0..n * n)
+            .map(|i| Mod::from(blocks[i % n][i / n]))
+            .collect(),
+    )
+}
+
+/// Advances combination of indices (ascending) below `n`, returns false when exhausted.
+fn next_combination(v: &mut [usize], n: usize) -> bool {
+    let k = v.len();
+    for i in (0..k).rev() {
+        if v[i] < n - k + i {
+            v[i] += 1;
+            for j in i + 1..k {
+                v[j] = v[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// Recovers Hill key matrix of `size` from aligned plaintext and ciphertext.
+///
+/// Non-letters are dropped and the longer text is truncated. Picks `size` plaintext blocks
+/// whose matrix `P` (blocks as columns) is invertible, then the key is `K = C·P⁻¹`.
+/// Returns the key in row-major order, as accepted by [`Hill::new`](crate::ciphers::Hill::new).
+pub fn known_plaintext(plain: &[u8], cipher: &[u8], size: usize) -> Result<Vec<u8>, String> {
+    if size == 0 {
+        return Err("block size is 0".into());
+    }
+
+    let (plain, cipher) = (letters(plain), letters(cipher));
+    let len = plain.len().min(cipher.len()) / size * size;
+    let (plain, cipher) = (&plain[..len], &cipher[..len]);
+    let blocks: Vec<_> = plain
+        .chunks_exact(size)
+        .zip(cipher.chunks_exact(size))
+        .collect();
+
+    // Repeated plaintext blocks can never be in the same invertible selection
+    let mut distinct: Vec<(&[u8], &[u8])> = Vec::new();
+    for &(p, c) in &blocks {
+        match distinct.iter().find(|(p_, _)| *p_ == p) {
+            Some((_, c_)) if *c_ != c => {
+                return Err(format!(
+                    "plaintext block {} is encrypted into different ciphertext blocks",
+                    String::from_utf8_lossy(&p.iter().map(|b| b + A_UPPER).collect::<Vec<_>>()),
+                ))
+            }
+            Some(_) => (),
+            None => distinct.push((p, c)),
+        }
+    }
+    if distinct.len() < size {
+        return Err(format!(
+            "need at least {} distinct plaintext blocks of {} letters, found {}",
+            size,
+            size,
+            distinct.len()
+        ));
+    }
+
+    let mut selection: Vec<_> = (0..size).collect();
+    for _ in 0..MAX_SELECTIONS {
+        let p = columns(&selection.iter().map(|&i| distinct[i].0).collect::<Vec<_>>());
+        if let Ok(p_inv) = p.inverse() {
+            let c = columns(&selection.iter().map(|&i| distinct[i].1).collect::<Vec<_>>());
+            return verify(&(&c * &p_inv), &blocks);
+        }
+
+        if !next_combination(&mut selection, distinct.len()) {
+            break;
+        }
+    }
+
+    Err("no selection of plaintext blocks is invertible".into())
+}
+
+/// Checks every block pair against recovered key.
+fn verify(key: &MatrixU8<MODULO>, blocks: &[(&[u8], &[u8])]) -> Result<Vec<u8>, String> {
+    let size = key.size();
+    let mut p = vec![Mod::from(0); size];
+    let mut out = vec![Mod::from(0); size];
+
+    for (i, (plain, cipher)) in blocks.iter().enumerate() {
+        for (a, &b) in p.iter_mut().zip(*plain) {
+            *a = b.into();
+        }
+        key.slice_mult(&p, &mut out);
+
+        if out.iter().zip(*cipher).any(|(&a, &b)| u8::from(a) != b) {
+            return Err(format!(
+                "recovered key does not match block {}, texts may not be aligned",
+                i + 1
+            ));
+        }
+    }
+
+    Ok(key.as_slice().iter().map(|&v| v.into()).collect())
+}
@@ -0,0 +1,109 @@
+pub mod affine;
+pub mod frequency;
+pub mod hill;
+pub mod playfair;
+pub mod quadgram;
+pub mod vigenere;
+
+const A_UPPER: u8 = b'A';
+const Z_UPPER: u8 = b'Z';
+const A_LOWER: u8 = b'a';
+const Z_LOWER: u8 = b'z';
+
+/// Reference language for scoring.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    English,
+    Indonesian,
+}
+
+/// Letter frequency (in percent) of English text.
+const ENGLISH: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+/// Letter frequency (in percent) of Indonesian text.
+const INDONESIAN: [f64; 26] = [
+    19.40, 2.60, 0.70, 4.20, 8.80, 0.20, 3.40, 2.40, 8.60, 0.90, 4.70, 3.40, 3.90, 9.60, 2.40,
+    2.60, 0.01, 4.20, 4.50, 5.20, 5.40, 0.20, 0.60, 0.03, 1.90, 0.05,
+];
+
+impl Language {
+    pub const ALL: [Self; 2] = [Self::English, Self::Indonesian];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Indonesian => "Indonesian",
+        }
+    }
+
+    /// Letter frequency in percent, indexed from A.
+    pub fn frequencies(self) -> &'static [f64; 26] {
+        match self {
+            Self::English => &ENGLISH,
+            Self::Indonesian => &INDONESIAN,
+        }
+    }
+
+    /// Expected index of coincidence of the language.
+    pub fn index_of_coincidence(self) -> f64 {
+        let f = self.frequencies();
+        let total: f64 = f.iter().sum();
+        f.iter().map(|v| (v / total) * (v / total)).sum()
+    }
+}
+
+/// Converts text into letter indices (A = 0), dropping non-letters.
+pub fn letters(text: &[u8]) -> Vec<u8> {
+    text.iter()
+        .filter_map(|&b| match b {
+            A_UPPER..=Z_UPPER => Some(b - A_UPPER),
+            A_LOWER..=Z_LOWER => Some(b - A_LOWER),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Counts letter indices.
+pub fn letter_counts(letters: impl IntoIterator<Item = u8>) -> [usize; 26] {
+    let mut ret = [0; 26];
+    for l in letters {
+        ret[l as usize] += 1;
+    }
+    ret
+}
+
+/// Index of coincidence of counted symbols.
+pub fn index_of_coincidence(counts: &[usize]) -> f64 {
+    let n: usize = counts.iter().sum();
+    if n < 2 {
+        return 0.0;
+    }
+
+    let s: usize = counts.iter().map(|&c| c * c.saturating_sub(1)).sum();
+    s as f64 / (n * (n - 1)) as f64
+}
+
+/// Chi-squared statistic of letter counts against a language.
+///
+/// Lower is closer to the language.
+pub fn chi_squared(counts: &[usize; 26], lang: Language) -> f64 {
+    let n: usize = counts.iter().sum();
+    if n == 0 {
+        return f64::INFINITY;
+    }
+
+    let f = lang.frequencies();
+    let total: f64 = f.iter().sum();
+    counts
+        .iter()
+        .zip(f)
+        .map(|(&c, &p)| {
+            let e = n as f64 * p / total;
+            (c as f64 - e) * (c as f64 - e) / e
+        })
+        .sum()
+}
@@ -0,0 +1,239 @@
+use crate::ciphers::{Decryptor, Playfair};
+
+use super::quadgram::Quadgrams;
+
+const A_UPPER: u8 = b'A';
+
+/// Letters of the keysquare, J is merged into I.
+const ALPHABET: &[u8; 25] = b"ABCDEFGHIKLMNOPQRSTUVWXYZ";
+
+/// Number of best squares kept.
+const BEST_COUNT: usize = 5;
+/// Iterations before temperature is lowered.
+const ITERATIONS_PER_STEP: usize = 5000;
+/// Temperature decrease per step.
+const TEMPERATURE_STEP: f64 = 0.2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// Keysquare letters in row-major order.
+    pub square: [u8; 25],
+    /// Quadgram log-probability of plaintext.
+    pub score: f64,
+    /// Decrypted text (uppercase letters).
+    pub plaintext: Vec<u8>,
+}
+
+/// Xorshift64* generator.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        })
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Ciphertext-only Playfair solver using simulated annealing.
+///
+/// Searching is done in chunks with [`step`](Self::step), so it can be interleaved
+/// with other work.
+#[derive(Debug, Clone)]
+pub struct PlayfairSolver {
+    cipher: Vec<u8>,
+    model: &'static Quadgrams,
+    rng: Rng,
+
+    square: [u8; 25],
+    score: f64,
+    start_temperature: f64,
+    temperature: f64,
+    iterations: usize,
+
+    best: Vec<Solution>,
+}
+
+impl PlayfairSolver {
+    /// Creates solver with a random starting square.
+    ///
+    /// Non-letters of ciphertext are dropped.
+    pub fn new(cipher: &[u8], seed: u64) -> Result<Self, String> {
+        let mut cipher: Vec<_> = cipher
+            .iter()
+            .filter(|b| b.is_ascii_alphabetic())
+            .map(|b| b.to_ascii_uppercase())
+            .collect();
+        if cipher.len() % 2 != 0 {
+            cipher.pop();
+        }
+        if cipher.len() < 4 {
+            return Err("ciphertext is too short".into());
+        }
+
+        let mut rng = Rng::new(seed);
+        let mut square = *ALPHABET;
+        for i in (1..square.len()).rev() {
+            square.swap(i, rng.below(i + 1));
+        }
+
+        // Scaled by length, as score difference grows with it
+        let start_temperature = 5. + 0.04 * (cipher.len() as f64 - 84.).max(0.);
+
+        let mut ret = Self {
+            cipher,
+            model: Quadgrams::english(),
+            rng,
+
+            square,
+            score: 0.,
+            start_temperature,
+            temperature: start_temperature,
+            iterations: 0,
+
+            best: Vec::new(),
+        };
+        let (score, plaintext) = ret.evaluate(&square);
+        ret.score = score;
+        ret.record(square, score, plaintext);
+        Ok(ret)
+    }
+
+    /// Total iterations done.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Current temperature.
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    /// Best squares found, highest score first.
+    pub fn best(&self) -> &[Solution] {
+        &self.best
+    }
+
+    /// Runs `iterations` more iterations.
+    pub fn step(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            let square = self.mutate();
+            let (score, plaintext) = self.evaluate(&square);
+
+            let delta = score - self.score;
+            if (delta >= 0.) || (self.rng.unit() < (delta / self.temperature).exp()) {
+                self.square = square;
+                self.score = score;
+                self.record(square, score, plaintext);
+            }
+
+            self.iterations += 1;
+            if self.iterations.is_multiple_of(ITERATIONS_PER_STEP) {
+                self.temperature -= TEMPERATURE_STEP;
+                if self.temperature <= 0. {
+                    // Reheat from best square
+                    self.temperature = self.start_temperature;
+                    self.square = self.best[0].square;
+                    self.score = self.best[0].score;
+                }
+            }
+        }
+    }
+
+    fn evaluate(&self, square: &[u8; 25]) -> (f64, Vec<u8>) {
+        let mut d = Playfair::new(square).unwrap();
+        let mut plaintext = Vec::with_capacity(self.cipher.len());
+        for &b in &self.cipher {
+            plaintext.extend_from_slice(d.decrypt_byte(b).unwrap());
+        }
+
+        let indices: Vec<_> = plaintext.iter().map(|b| b - A_UPPER).collect();
+        (self.model.score(&indices), plaintext)
+    }
+
+    fn mutate(&mut self) -> [u8; 25] {
+        let mut square = self.square;
+        let cell = |r: usize, c: usize| r * 5 + c;
+
+        match self.rng.below(50) {
+            // Swap rows
+            0 => {
+                let (a, b) = (self.rng.below(5), self.rng.below(5));
+                for c in 0..5 {
+                    square.swap(cell(a, c), cell(b, c));
+                }
+            }
+            // Swap columns
+            1 => {
+                let (a, b) = (self.rng.below(5), self.rng.below(5));
+                for r in 0..5 {
+                    square.swap(cell(r, a), cell(r, b));
+                }
+            }
+            // Flip top to bottom
+            2 => {
+                for r in 0..2 {
+                    for c in 0..5 {
+                        square.swap(cell(r, c), cell(4 - r, c));
+                    }
+                }
+            }
+            // Flip left to right
+            3 => {
+                for r in 0..5 {
+                    for c in 0..2 {
+                        square.swap(cell(r, c), cell(r, 4 - c));
+                    }
+                }
+            }
+            // Rotate half turn
+            4 => square.reverse(),
+            // Swap letters
+            _ => {
+                let (a, b) = (self.rng.below(25), self.rng.below(25));
+                square.swap(a, b);
+            }
+        }
+
+        square
+    }
+
+    fn record(&mut self, square: [u8; 25], score: f64, plaintext: Vec<u8>) {
+        if self.best.iter().any(|s| s.square == square) {
+            return;
+        }
+        if (self.best.len() >= BEST_COUNT) && (self.best[BEST_COUNT - 1].score >= score) {
+            return;
+        }
+
+        let i = self.best.partition_point(|s| s.score >= score);
+        self.best.insert(
+            i,
+            Solution {
+                square,
+                score,
+                plaintext,
+            },
+        );
+        self.best.truncate(BEST_COUNT);
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::OnceLock;
+
+use super::letters;
+
+const SIZE: usize = 26 * 26 * 26 * 26;
+
+/// Probability given to quadgrams never seen in corpus, relative to one occurrence.
+const FLOOR: f64 = 0.01;
+
+/// Quadgram log-probability model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quadgrams {
+    /// Log10 probability, indexed by letter indices in base 26.
+    table: Vec<f32>,
+}
+
+impl Quadgrams {
+    /// Builds model from a count table, one `QUAD count` pair per line.
+    ///
+    /// Empty lines and lines starting with `#` are skipped.
+    pub fn from_counts(table: &str) -> Result<Self, String> {
+        let mut counts = vec![0u64; SIZE];
+        for (i, line) in table.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line.split_once(' ').and_then(|(quad, count)| {
+                let quad = letters(quad.as_bytes());
+                let count: u64 = count.trim().parse().ok()?;
+                (quad.len() == 4).then(|| (index(&quad), count))
+            });
+            let Some((quad, count)) = parsed else {
+                return Err(format!("invalid quadgram count at line {}", i + 1));
+            };
+            counts[quad] += count;
+        }
+        Ok(Self::from_table(counts))
+    }
+
+    fn from_table(counts: Vec<u64>) -> Self {
+        let total = counts.iter().sum::<u64>().max(1) as f64;
+        let floor = (FLOOR / total).log10() as f32;
+        Self {
+            table: counts
+                .into_iter()
+                .map(|c| match c {
+                    0 => floor,
+                    c => (c as f64 / total).log10() as f32,
+                })
+                .collect(),
+        }
+    }
+
+    /// Model of the bundled English quadgram counts, built on first use.
+    ///
+    /// Counts are taken from the Rust books, see `scripts/english_quadgrams.py`.
+    pub fn english() -> &'static Self {
+        static ENGLISH: OnceLock<Quadgrams> = OnceLock::new();
+        ENGLISH.get_or_init(|| Self::from_counts(include_str!("english_quadgrams.txt")).unwrap())
+    }
+
+    /// Log10 probability of text, higher is more like the corpus.
+    ///
+    /// Text must be letter indices (see [`letters`]).
+    pub fn score(&self, text: &[u8]) -> f64 {
+        text.windows(4).map(|w| self.table[index(w)] as f64).sum()
+    }
+}
+
+fn index(w: &[u8]) -> usize {
+    w.iter().fold(0, |a, &b| a * 26 + b as usize)
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use super::{chi_squared, index_of_coincidence, letter_counts, letters, Language};
+
+const A_UPPER: u8 = b'A';
+
+/// Weight of Kasiski examination in [`KeyCandidate::score`].
+const KASISKI_WEIGHT: f64 = 0.5;
+
+/// Spacings between repeated trigrams (Kasiski examination).
+///
+/// Text must be letter indices (see [`letters`]).
+pub fn kasiski_spacings(text: &[u8]) -> Vec<usize> {
+    let mut last = HashMap::new();
+    let mut ret = Vec::new();
+
+    for (i, w) in text.windows(3).enumerate() {
+        if let Some(j) = last.insert([w[0], w[1], w[2]], i) {
+            ret.push(i - j);
+        }
+    }
+
+    ret
+}
+
+/// Average index of coincidence of columns if text is split into `length` columns.
+///
+/// Text must be letter indices.
+pub fn column_ioc(text: &[u8], length: usize) -> f64 {
+    let s: f64 = (0..length)
+        .map(|i| index_of_coincidence(&letter_counts(text.iter().skip(i).step_by(length).copied())))
+        .sum();
+    s / length as f64
+}
+
+/// Friedman test estimation of key length.
+///
+/// Text must be letter indices.
+pub fn friedman_estimate(text: &[u8], lang: Language) -> f64 {
+    let ioc = index_of_coincidence(&letter_counts(text.iter().copied()));
+    let (kp, kr) = (lang.index_of_coincidence(), 1. / 26.);
+    if ioc <= kr {
+        return f64::INFINITY;
+    }
+    (kp - kr) / (ioc - kr)
+}
+
+/// Recovers most probable key of given length using chi-squared test of each column.
+///
+/// Text must be letter indices.
+pub fn recover_key(text: &[u8], length: usize, lang: Language) -> Vec<u8> {
+    (0..length)
+        .map(|i| {
+            let counts = letter_counts(text.iter().skip(i).step_by(length).copied());
+            let (shift, _) = (0..26)
+                .map(|k| {
+                    let mut shifted = [0; 26];
+                    for (j, &c) in counts.iter().enumerate() {
+                        shifted[(j + 26 - k) % 26] = c;
+                    }
+                    (k, chi_squared(&shifted, lang))
+                })
+                .fold((0, f64::INFINITY), |a, b| if b.1 < a.1 { b } else { a });
+            shift as u8 + A_UPPER
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyCandidate {
+    /// Key length.
+    pub length: usize,
+    /// Average index of coincidence of columns.
+    pub ioc: f64,
+    /// Fraction of repeated trigram spacings divisible by key length.
+    pub kasiski: f64,
+    /// Combined score, higher is more probable.
+    pub score: f64,
+    /// Most probable key (uppercase letters).
+    pub key: Vec<u8>,
+}
+
+/// Ranks key lengths up to `max_length` and recovers the key of each.
+///
+/// Key length is scored by column index of coincidence relative to the language,
+/// plus fraction of Kasiski spacings it divides.
+pub fn analyze(text: &[u8], max_length: usize, lang: Language) -> Vec<KeyCandidate> {
+    let text = letters(text);
+    let spacings = kasiski_spacings(&text);
+    let lang_ioc = lang.index_of_coincidence();
+
+    let max_length = max_length.min(text.len() / 2).max(1);
+    let mut ret: Vec<_> = (1..=max_length)
+        .map(|length| {
+            let ioc = column_ioc(&text, length);
+            let kasiski = if (length == 1) || spacings.is_empty() {
+                0.
+            } else {
+                let n = spacings.iter().filter(|&&s| s % length == 0).count();
+                n as f64 / spacings.len() as f64
+            };
+
+            KeyCandidate {
+                length,
+                ioc,
+                kasiski,
+                score: ioc / lang_ioc + kasiski * KASISKI_WEIGHT,
+                key: recover_key(&text, length, lang),
+            }
+        })
+        .collect();
+
+    ret.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ret
+}
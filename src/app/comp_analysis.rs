@@ -0,0 +1,537 @@
+use std::io::Write;
+use std::rc::Rc;
+
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use tugas_kripto_1a::analysis::affine::{brute_force, known_plaintext, AffineCandidate};
+use tugas_kripto_1a::analysis::hill;
+use tugas_kripto_1a::analysis::playfair::{PlayfairSolver, Solution};
+use tugas_kripto_1a::analysis::vigenere::{analyze, friedman_estimate, KeyCandidate};
+use tugas_kripto_1a::analysis::{letters, Language};
+use tugas_kripto_1a::ciphers::*;
+
+#[derive(Properties, PartialEq)]
+pub struct LanguageSelectProps {
+    select: NodeRef,
+}
+
+#[function_component(LanguageSelect)]
+pub fn language_select(props: &LanguageSelectProps) -> Html {
+    html! {
+        <select ref={ props.select.clone() }>
+            { for Language::ALL.iter().enumerate().map(|(i, l)| html! {
+                <option value={ i.to_string() }> { l.name() } </option>
+            }) }
+        </select>
+    }
+}
+
+pub fn selected_language(select: &NodeRef) -> Language {
+    select
+        .cast::<HtmlSelectElement>()
+        .and_then(|s| s.value().parse::<usize>().ok())
+        .and_then(|i| Language::ALL.get(i).copied())
+        .unwrap_or_default()
+}
+
+fn vigenere_decrypt(key: &str, cipher: &str) -> Result<String, String> {
+    let d = Vignere::new(key.as_bytes())?;
+    let mut w = DecryptWriter::new(
+        Vec::new(),
+        <_ as Decryptor>::preserve(d, |b| b.is_ascii_alphabetic()),
+    );
+    w.write_all(cipher.as_bytes())
+        .and_then(|_| w.finish())
+        .map(|v| String::from_iter(v.into_iter().map(char::from)))
+        .map_err(|e| e.to_string())
+}
+
+#[function_component(VigenereAnalysis)]
+pub fn vigenere_analysis() -> Html {
+    let textbox = use_node_ref();
+    let output = use_node_ref();
+    let max_length = use_node_ref();
+    let language = use_node_ref();
+    let key_input = use_node_ref();
+
+    let candidates = use_state(|| Rc::new(Vec::<KeyCandidate>::new()));
+    let friedman = use_state_eq(|| None::<f64>);
+    let err_happened = use_state_eq(|| false);
+
+    let decrypt_with = {
+        let textbox = textbox.clone();
+        let output = output.clone();
+        let key_input = key_input.clone();
+        let err_happened = err_happened.setter();
+
+        Callback::from(move |key: Option<AttrValue>| {
+            let (Some(textbox), Some(output), Some(key_input)) = (
+                textbox.cast::<HtmlTextAreaElement>(),
+                output.cast::<HtmlTextAreaElement>(),
+                key_input.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+
+            if let Some(key) = key {
+                key_input.set_value(&key);
+            }
+
+            let plain = vigenere_decrypt(&key_input.value(), &textbox.value());
+            err_happened.set(plain.is_err());
+            match plain {
+                Ok(v) => output.set_value(&v),
+                Err(e) => output.set_value(&format!("Error, {}", e)),
+            }
+        })
+    };
+
+    let analyze_ = {
+        let textbox = textbox.clone();
+        let max_length = max_length.clone();
+        let language = language.clone();
+        let candidates = candidates.setter();
+        let friedman = friedman.setter();
+        let decrypt_with = decrypt_with.clone();
+
+        Callback::from(move |_| {
+            let (Some(textbox), Some(max_length)) = (
+                textbox.cast::<HtmlTextAreaElement>(),
+                max_length.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+
+            let max_length = max_length.value().trim().parse().unwrap_or(20);
+            let lang = selected_language(&language);
+            let text = textbox.value();
+            friedman.set(Some(friedman_estimate(&letters(text.as_bytes()), lang)));
+            let v = analyze(text.as_bytes(), max_length, lang);
+            if let Some(c) = v.first() {
+                decrypt_with.emit(Some(String::from_utf8_lossy(&c.key).into_owned().into()));
+            }
+            candidates.set(Rc::new(v));
+        })
+    };
+
+    let rows = candidates.iter().map(|c| {
+        let key = AttrValue::from(String::from_utf8_lossy(&c.key).into_owned());
+        let on_click = {
+            let key = key.clone();
+            decrypt_with.reform(move |_| Some(key.clone()))
+        };
+
+        html! {
+            <tr>
+                <td> { c.length } </td>
+                <td> { format!("{:.4}", c.ioc) } </td>
+                <td> { format!("{:.1}%", c.kasiski * 100.) } </td>
+                <td> { format!("{:.3}", c.score) } </td>
+                <td class="key"> { key } </td>
+                <td> <button onclick={ on_click }> { "Use" } </button> </td>
+            </tr>
+        }
+    });
+
+    html! {
+        <div class="cipher_box">
+            <div class="key_container">
+                <label> { "Max key length:" } </label>
+                <input ref={ max_length } type="number" min="1" value="20" />
+                <label> { "Language:" } </label>
+                <LanguageSelect select={ language } />
+                <label> { "Key:" } </label>
+                <input ref={ key_input } />
+            </div>
+            <textarea ref={ textbox } cols=80 rows=10 placeholder="Vigenere ciphertext" />
+            <textarea ref={ output }
+                class={ classes!(if *err_happened { Some("error") } else { None } ) }
+                readonly=true
+                cols=80 rows=10
+                style="resize: none;"
+            />
+            <div class="action_container">
+                <button onclick={ analyze_ }> { "Analyze" } </button>
+                <button onclick={ decrypt_with.reform(|_| None) }> { "Decrypt" } </button>
+            </div>
+            if let Some(v) = *friedman {
+                <p>
+                    if v.is_finite() {
+                        { format!("Friedman test estimates key length {:.1}.", v) }
+                    } else {
+                        { "Friedman test gives no estimate, text is too close to random." }
+                    }
+                </p>
+            }
+            if !candidates.is_empty() {
+                <table class="analysis_table">
+                    <tr>
+                        <th> { "Length" } </th>
+                        <th> { "Column IoC" } </th>
+                        <th> { "Kasiski" } </th>
+                        <th> { "Score" } </th>
+                        <th> { "Key" } </th>
+                        <th />
+                    </tr>
+                    { for rows }
+                </table>
+            }
+        </div>
+    }
+}
+
+/// Number of brute force candidates shown.
+const AFFINE_CANDIDATES: usize = 10;
+
+#[derive(Properties, PartialEq)]
+pub struct AffineAnalysisProps {
+    /// Called with `(m, n)` of chosen key, which is for the Latin alphabet.
+    pub on_use: Callback<(u8, u8)>,
+}
+
+#[function_component(AffineAnalysis)]
+pub fn affine_analysis(props: &AffineAnalysisProps) -> Html {
+    let textbox = use_node_ref();
+    let language = use_node_ref();
+    let plain = use_node_ref();
+    let cipher = use_node_ref();
+
+    let candidates = use_state(|| Rc::new(Vec::<AffineCandidate>::new()));
+    let solutions = use_state(|| Ok(Vec::<(u8, u8)>::new()));
+
+    let brute_force_ = {
+        let textbox = textbox.clone();
+        let language = language.clone();
+        let candidates = candidates.setter();
+
+        Callback::from(move |_| {
+            if let Some(textbox) = textbox.cast::<HtmlTextAreaElement>() {
+                let mut v = brute_force(textbox.value().as_bytes(), selected_language(&language));
+                v.truncate(AFFINE_CANDIDATES);
+                candidates.set(Rc::new(v));
+            }
+        })
+    };
+
+    let solve = {
+        let plain = plain.clone();
+        let cipher = cipher.clone();
+        let solutions = solutions.setter();
+
+        Callback::from(move |_| {
+            let (Some(plain), Some(cipher)) = (
+                plain.cast::<HtmlInputElement>(),
+                cipher.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+
+            let (plain, cipher) = (plain.value(), cipher.value());
+            solutions.set(
+                match (
+                    <[u8; 2]>::try_from(plain.trim().as_bytes()),
+                    <[u8; 2]>::try_from(cipher.trim().as_bytes()),
+                ) {
+                    (Ok(p), Ok(c)) => known_plaintext(p, c),
+                    _ => Err("plaintext and ciphertext must be 2 letters".into()),
+                },
+            );
+        })
+    };
+
+    let use_button = |m: u8, n: u8| {
+        html! {
+            <button onclick={ props.on_use.reform(move |_| (m, n)) }> { "Use" } </button>
+        }
+    };
+
+    let rows = candidates.iter().map(|c| {
+        html! {
+            <tr>
+                <td> { c.m } </td>
+                <td> { c.n } </td>
+                <td> { format!("{:.1}", c.score) } </td>
+                <td class="key"> { String::from_utf8_lossy(&c.preview) } </td>
+                <td> { use_button(c.m, c.n) } </td>
+            </tr>
+        }
+    });
+
+    html! {
+        <details class="cipher_box">
+            <summary> { "Cryptanalysis (A-Z)" } </summary>
+            <div class="key_container">
+                <label> { "Language:" } </label>
+                <LanguageSelect select={ language } />
+            </div>
+            <textarea ref={ textbox } cols=80 rows=5 placeholder="Affine ciphertext" />
+            <div class="action_container">
+                <button onclick={ brute_force_ }> { "Brute Force" } </button>
+            </div>
+            if !candidates.is_empty() {
+                <table class="analysis_table">
+                    <tr>
+                        <th> { "M" } </th>
+                        <th> { "N" } </th>
+                        <th> { "Chi-squared" } </th>
+                        <th> { "Plaintext" } </th>
+                        <th />
+                    </tr>
+                    { for rows }
+                </table>
+            }
+            <div class="format_container">
+                <label> { "Known plaintext:" } </label>
+                <input ref={ plain } maxlength="2" size="2" placeholder="TH" />
+                <label> { "Ciphertext:" } </label>
+                <input ref={ cipher } maxlength="2" size="2" />
+                <button onclick={ solve }> { "Solve" } </button>
+            </div>
+            {
+                match &*solutions {
+                    Ok(v) => html! {
+                        for v.iter().map(|&(m, n)| html! {
+                            <div class="format_container">
+                                <label> { format!("M = {}, N = {}", m, n) } </label>
+                                { use_button(m, n) }
+                            </div>
+                        })
+                    },
+                    Err(e) => html! { <p class="error"> { format!("Error, {}", e) } </p> },
+                }
+            }
+        </details>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct HillAnalysisProps {
+    /// Called with recovered key matrix, formatted like the key input.
+    ///
+    /// Key is for the Latin alphabet, non-letters of the texts are dropped.
+    pub on_use: Callback<AttrValue>,
+}
+
+#[function_component(HillAnalysis)]
+pub fn hill_analysis(props: &HillAnalysisProps) -> Html {
+    let plain = use_node_ref();
+    let cipher = use_node_ref();
+    let size = use_node_ref();
+
+    let result = use_state(|| None::<Result<(usize, Vec<u8>), String>>);
+
+    let solve = {
+        let plain = plain.clone();
+        let cipher = cipher.clone();
+        let size = size.clone();
+        let result = result.setter();
+
+        Callback::from(move |_| {
+            let (Some(plain), Some(cipher), Some(size)) = (
+                plain.cast::<HtmlTextAreaElement>(),
+                cipher.cast::<HtmlTextAreaElement>(),
+                size.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+
+            result.set(Some(
+                size.value()
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| e.to_string())
+                    .and_then(|size| {
+                        let key = hill::known_plaintext(
+                            plain.value().as_bytes(),
+                            cipher.value().as_bytes(),
+                            size,
+                        )?;
+                        Ok((size, key))
+                    }),
+            ));
+        })
+    };
+
+    let output = match &*result {
+        None => html! {},
+        Some(Ok((size, key))) => {
+            let text = AttrValue::from(
+                key.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+
+            html! {
+                <>
+                    <table class="analysis_table">
+                        { for key.chunks(*size).map(|row| html! {
+                            <tr>
+                                { for row.iter().map(|v| html! { <td class="key"> { v } </td> }) }
+                            </tr>
+                        }) }
+                    </table>
+                    <div class="action_container">
+                        <button onclick={ props.on_use.reform(move |_| text.clone()) }>
+                            { "Use" }
+                        </button>
+                    </div>
+                </>
+            }
+        }
+        Some(Err(e)) => html! { <p class="error"> { format!("Error, {}", e) } </p> },
+    };
+
+    html! {
+        <details class="cipher_box">
+            <summary> { "Known Plaintext Attack (A-Z)" } </summary>
+            <div class="key_container">
+                <label> { "Block size:" } </label>
+                <input ref={ size } type="number" min="1" value="2" />
+            </div>
+            <textarea ref={ plain } cols=80 rows=5 placeholder="Known plaintext" />
+            <textarea ref={ cipher } cols=80 rows=5 placeholder="Matching ciphertext" />
+            <div class="action_container">
+                <button onclick={ solve }> { "Solve" } </button>
+            </div>
+            { output }
+        </details>
+    }
+}
+
+/// Solver iterations between UI updates.
+const PLAYFAIR_CHUNK: usize = 2000;
+/// Length of plaintext shown per square.
+const PLAYFAIR_PREVIEW: usize = 60;
+
+/// Yields to the browser event loop.
+async fn next_tick() {
+    let p = js_sys::Promise::new(&mut |resolve, _| {
+        if let Some(w) = web_sys::window() {
+            let _ = w.set_timeout_with_callback(&resolve);
+        }
+    });
+    let _ = JsFuture::from(p).await;
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PlayfairAnalysisProps {
+    /// Called with letters of chosen keysquare.
+    pub on_use: Callback<AttrValue>,
+}
+
+#[function_component(PlayfairAnalysis)]
+pub fn playfair_analysis(props: &PlayfairAnalysisProps) -> Html {
+    let textbox = use_node_ref();
+
+    // Id of the current search, each loop stops once it changes
+    let generation = use_mut_ref(|| 0u64);
+    let is_running = use_state_eq(|| false);
+    let progress = use_state(|| Rc::new((0usize, Vec::<Solution>::new())));
+    let error = use_state_eq(|| None::<String>);
+
+    {
+        // Stop searching when unmounted
+        let generation = generation.clone();
+        use_effect_with_deps(move |_| move || *generation.borrow_mut() += 1, ());
+    }
+
+    let start = {
+        let textbox = textbox.clone();
+        let generation = generation.clone();
+        let is_running = is_running.setter();
+        let progress = progress.setter();
+        let error = error.setter();
+
+        Callback::from(move |_| {
+            let Some(textbox) = textbox.cast::<HtmlTextAreaElement>() else {
+                return;
+            };
+            let seed = (js_sys::Math::random() * u64::MAX as f64) as u64;
+            let mut solver = match PlayfairSolver::new(textbox.value().as_bytes(), seed) {
+                Ok(v) => v,
+                Err(e) => {
+                    error.set(Some(e));
+                    return;
+                }
+            };
+            error.set(None);
+
+            let id = {
+                let mut generation = generation.borrow_mut();
+                *generation += 1;
+                *generation
+            };
+            is_running.set(true);
+
+            let generation = generation.clone();
+            let progress = progress.clone();
+            spawn_local(async move {
+                while *generation.borrow() == id {
+                    solver.step(PLAYFAIR_CHUNK);
+                    progress.set(Rc::new((solver.iterations(), solver.best().to_vec())));
+                    next_tick().await;
+                }
+            });
+        })
+    };
+
+    let stop = {
+        let generation = generation.clone();
+        let is_running = is_running.setter();
+
+        Callback::from(move |_| {
+            *generation.borrow_mut() += 1;
+            is_running.set(false);
+        })
+    };
+
+    let rows = progress.1.iter().map(|s| {
+        let square = AttrValue::from(String::from_utf8_lossy(&s.square).into_owned());
+        let on_click = {
+            let square = square.clone();
+            props.on_use.reform(move |_| square.clone())
+        };
+        let plaintext = &s.plaintext[..s.plaintext.len().min(PLAYFAIR_PREVIEW)];
+
+        html! {
+            <tr>
+                <td> { format!("{:.1}", s.score) } </td>
+                <td class="key"> { square } </td>
+                <td class="key"> { String::from_utf8_lossy(plaintext) } </td>
+                <td> <button onclick={ on_click }> { "Use" } </button> </td>
+            </tr>
+        }
+    });
+
+    html! {
+        <details class="cipher_box">
+            <summary> { "Cryptanalysis" } </summary>
+            <textarea ref={ textbox } cols=80 rows=5 placeholder="Playfair ciphertext" />
+            <div class="action_container">
+                if *is_running {
+                    <button onclick={ stop }> { "Stop" } </button>
+                } else {
+                    <button onclick={ start }> { "Search" } </button>
+                }
+            </div>
+            if let Some(e) = &*error {
+                <p class="error"> { format!("Error, {}", e) } </p>
+            }
+            if progress.0 > 0 {
+                <p> { format!("Iterations: {}", progress.0) } </p>
+                <table class="analysis_table">
+                    <tr>
+                        <th> { "Score" } </th>
+                        <th> { "Square" } </th>
+                        <th> { "Plaintext" } </th>
+                        <th />
+                    </tr>
+                    { for rows }
+                </table>
+            }
+        </details>
+    }
+}
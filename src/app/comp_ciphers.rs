@@ -1,152 +1,489 @@
-use js_sys::Uint8Array;
+use std::rc::Rc;
+
+use js_sys::{Array, Uint8Array};
+use num_integer::Integer;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::{spawn_local, JsFuture};
-use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use web_sys::{
+    Blob, BlobPropertyBag, HtmlAnchorElement, HtmlInputElement, HtmlSelectElement,
+    HtmlTextAreaElement, Url,
+};
 use yew::prelude::*;
 
-use crate::ciphers::*;
-use crate::parsers::list_u8;
+use tugas_kripto_1a::analysis::frequency::Symbols;
+use tugas_kripto_1a::ciphers::*;
+use tugas_kripto_1a::encoding::Encoding;
+use tugas_kripto_1a::parsers::list_u8;
+use tugas_kripto_1a::util::MatrixZn;
+
+use super::comp_analysis::{AffineAnalysis, HillAnalysis, PlayfairAnalysis};
+use super::comp_frequency::FrequencyPanel;
+use super::comp_keyview::{grid_rows, matrix_rows, tabula_rows, KeyTable, StepsTable};
 
 #[derive(Properties, PartialEq)]
 pub struct CipherBoxProps {
     encryptor: Callback<(), Result<Box<dyn Encryptor>, AttrValue>>,
     decryptor: Callback<(), Result<Box<dyn Decryptor>, AttrValue>>,
 
+    /// Show output layout controls.
+    #[prop_or(true)]
+    formatting: bool,
+    /// Symbols kept in place by the preserving layout, Latin letters if not given.
+    #[prop_or_default]
+    alphabet: Option<Callback<(), Result<Alphabet, AttrValue>>>,
+    /// Visualization of the current key, shown below the key inputs.
+    #[prop_or_default]
+    key_view: Html,
+
     pub children: Children,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputLayout {
+    Format(Layout),
+    Preserve,
+}
+
+fn output_layout(
+    select: &NodeRef,
+    size: &NodeRef,
+    per_line: &NodeRef,
+) -> Result<OutputLayout, AttrValue> {
+    let (Some(select), Some(size), Some(per_line)) = (
+        select.cast::<HtmlSelectElement>(),
+        size.cast::<HtmlInputElement>(),
+        per_line.cast::<HtmlInputElement>(),
+    ) else {
+        return Err(AttrValue::from("internal error"));
+    };
+
+    Ok(match &*select.value() {
+        "raw" => OutputLayout::Format(Layout::Raw),
+        "preserve" => OutputLayout::Preserve,
+        _ => {
+            let (size, per_line) = match size
+                .value()
+                .trim()
+                .parse::<usize>()
+                .and_then(|size| Ok((size, per_line.value().trim().parse()?)))
+            {
+                Ok(v) => v,
+                Err(e) => return Err(<_>::from(e.to_string())),
+            };
+            OutputLayout::Format(Layout::Grouped { size, per_line })
+        }
+    })
+}
+
+#[derive(Properties, PartialEq)]
+pub struct EncodingSelectProps {
+    select: NodeRef,
+    #[prop_or_default]
+    onchange: Callback<Event>,
+}
+
+#[function_component(EncodingSelect)]
+pub fn encoding_select(props: &EncodingSelectProps) -> Html {
+    html! {
+        <select ref={ props.select.clone() } onchange={ props.onchange.clone() }>
+            { for Encoding::ALL.iter().enumerate().map(|(i, e)| html! {
+                <option value={ i.to_string() }> { e.name() } </option>
+            }) }
+        </select>
+    }
+}
+
+fn selected_encoding(select: &NodeRef) -> Encoding {
+    select
+        .cast::<HtmlSelectElement>()
+        .and_then(|s| s.value().parse::<usize>().ok())
+        .and_then(|i| Encoding::ALL.get(i).copied())
+        .unwrap_or_default()
+}
+
+/// Cipher in both directions, for ciphers picked at runtime.
+trait Cipher: Encryptor + Decryptor {}
+
+impl<T: Encryptor + Decryptor> Cipher for T {}
+
+/// Alphabet selection of a cipher tab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphabetInput {
+    select: NodeRef,
+    symbols: NodeRef,
+}
+
+impl AlphabetInput {
+    fn read(&self) -> Result<Alphabet, AttrValue> {
+        let (Some(select), Some(symbols)) = (
+            self.select.cast::<HtmlSelectElement>(),
+            self.symbols.cast::<HtmlInputElement>(),
+        ) else {
+            return Err(AttrValue::from("internal error"));
+        };
+
+        let symbols = match select.value().parse::<usize>() {
+            Ok(i) if i < Alphabet::PRESETS.len() => Alphabet::PRESETS[i].1.to_vec(),
+            _ => symbols.value().into_bytes(),
+        };
+        Ok(Alphabet::new(&symbols)?)
+    }
+
+    /// Selects the Latin alphabet, which keys from cryptanalysis are for.
+    fn select_latin(&self) {
+        if let Some(select) = self.select.cast::<HtmlSelectElement>() {
+            select.set_value("0");
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AlphabetSelectProps {
+    input: AlphabetInput,
+    /// Called when the selected alphabet may have changed.
+    #[prop_or_default]
+    onchange: Callback<()>,
+}
+
+#[function_component(AlphabetSelect)]
+pub fn alphabet_select(props: &AlphabetSelectProps) -> Html {
+    html! {
+        <>
+            <label> { "Alphabet:" } </label>
+            <select ref={ props.input.select.clone() } onchange={ props.onchange.reform(|_| ()) }>
+                { for Alphabet::PRESETS.iter().enumerate().map(|(i, (name, _))| html! {
+                    <option value={ i.to_string() }> { *name } </option>
+                }) }
+                <option value="custom"> { "Custom" } </option>
+            </select>
+            <label> { "Custom symbols:" } </label>
+            <input
+                ref={ props.input.symbols.clone() }
+                placeholder="Used if alphabet is Custom"
+                oninput={ props.onchange.reform(|_| ()) }
+            />
+        </>
+    }
+}
+
+/// Result bytes kept for downloading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Download {
+    /// File name, without extension for text results.
+    name: String,
+    data: Vec<u8>,
+    /// Result of the text box, saved in the output encoding.
+    text: bool,
+}
+
+impl Download {
+    /// Name and content of the saved file.
+    ///
+    /// UTF-8 and Latin-1 text is saved as the raw bytes.
+    fn file(&self, encoding: Encoding) -> (String, Vec<u8>) {
+        if !self.text {
+            return (self.name.clone(), self.data.clone());
+        }
+
+        let name = format!("{}.{}", self.name, encoding.extension());
+        match encoding {
+            Encoding::Utf8 | Encoding::Latin1 => (name, self.data.clone()),
+            _ => (name, encoding.encode(&self.data).0.into_bytes()),
+        }
+    }
+}
+
+/// Name of encrypted file.
+fn encrypted_name(name: &str) -> String {
+    format!("{}.enc", name)
+}
+
+/// Name of decrypted file, undoing [`encrypted_name`] if possible.
+fn decrypted_name(name: &str) -> String {
+    match name.strip_suffix(".enc") {
+        Some(v) if !v.is_empty() => v.to_owned(),
+        _ => format!("{}.dec", name),
+    }
+}
+
+/// Saves bytes as a file through a temporary object URL.
+fn save_file(name: &str, data: &[u8]) -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+
+    let parts = Array::of1(&Uint8Array::from(data));
+    let options = BlobPropertyBag::new();
+    options.set_type("application/octet-stream");
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let a: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    a.set_href(&url);
+    a.set_download(name);
+    a.click();
+
+    Url::revoke_object_url(&url)
+}
+
+fn is_checked(checkbox: &NodeRef) -> bool {
+    checkbox
+        .cast::<HtmlInputElement>()
+        .is_some_and(|v| v.checked())
+}
+
 #[function_component(CipherBox)]
 pub fn cipher_box(props: &CipherBoxProps) -> Html {
     fn encrypt(
-        mut e: Box<dyn Encryptor>,
+        e: &mut dyn Encryptor,
         plain: impl IntoIterator<Item = u8>,
-    ) -> Result<Vec<u8>, ()> {
+    ) -> Result<Vec<u8>, CipherError> {
         let mut cipher = Vec::new();
-        let mut err_happen = false;
 
         for b in plain {
-            if let Ok(s) = e.encrypt_byte(b) {
-                cipher.extend_from_slice(s);
-            } else {
-                err_happen = true;
-                break;
-            }
+            cipher.extend_from_slice(e.encrypt_byte(b)?);
         }
+        cipher.extend_from_slice(&e.encrypt_finish()?);
 
-        if !err_happen {
-            if let Ok(v) = e.encrypt_finish() {
-                cipher.extend_from_slice(&v);
-            } else {
-                err_happen = true;
-            }
-        }
-
-        if err_happen {
-            Err(())
-        } else {
-            Ok(cipher)
-        }
+        Ok(cipher)
     }
 
     fn decrypt(
-        mut d: Box<dyn Decryptor>,
+        d: &mut dyn Decryptor,
         cipher: impl IntoIterator<Item = u8>,
-    ) -> Result<Vec<u8>, ()> {
+    ) -> Result<Vec<u8>, CipherError> {
         let mut plain = Vec::new();
-        let mut err_happen = false;
 
         for b in cipher {
-            if let Ok(s) = d.decrypt_byte(b) {
-                plain.extend_from_slice(s);
-            } else {
-                err_happen = true;
-                break;
-            }
+            plain.extend_from_slice(d.decrypt_byte(b)?);
         }
+        plain.extend_from_slice(&d.decrypt_finish()?);
 
-        if !err_happen {
-            if let Ok(v) = d.decrypt_finish() {
-                plain.extend_from_slice(&v);
-            } else {
-                err_happen = true;
-            }
-        }
-
-        if err_happen {
-            Err(())
-        } else {
-            Ok(plain)
-        }
+        Ok(plain)
     }
 
     let file_input = use_node_ref();
     let textbox = use_node_ref();
     let output = use_node_ref();
 
+    let layout_select = use_node_ref();
+    let group_size = use_node_ref();
+    let group_per_line = use_node_ref();
+
+    let input_encoding = use_node_ref();
+    let output_encoding = use_node_ref();
+
     let err_happened = use_state_eq(|| false);
+    let lossy = use_state_eq(|| false);
+    let download = use_state(|| None::<Rc<Download>>);
+    let analyzed = use_state(|| None::<Rc<Vec<u8>>>);
+    let show_steps = use_node_ref();
+    let steps = use_state(|| None::<Rc<Vec<TraceStep>>>);
 
-    let encrypt_ = {
-        let textbox = textbox.clone();
+    let show_output = {
         let output = output.clone();
+        let output_encoding = output_encoding.clone();
         let err_happened = err_happened.setter();
+        let lossy = lossy.setter();
+        let download = download.setter();
+
+        Callback::from(move |result: Result<Download, AttrValue>| {
+            let Some(output) = output.cast::<HtmlTextAreaElement>() else {
+                return;
+            };
+
+            err_happened.set(result.is_err());
+            match result {
+                Ok(v) => {
+                    let (text, is_lossy) = selected_encoding(&output_encoding).encode(&v.data);
+                    output.set_value(&text);
+                    lossy.set(is_lossy);
+                    download.set(Some(Rc::new(v)));
+                }
+                Err(e) => {
+                    output.set_value(&format!("Error, {}", e));
+                    lossy.set(false);
+                    download.set(None);
+                }
+            }
+        })
+    };
 
-        let encryptor = props.encryptor.clone();
+    let reencode_output = {
+        let download = download.clone();
+        let show_output = show_output.clone();
 
-        Callback::from(move |_| {
-            if let (Some(textbox), Some(output)) = (
-                textbox.cast::<HtmlTextAreaElement>(),
-                output.cast::<HtmlTextAreaElement>(),
-            ) {
-                let e = match encryptor.emit(()) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        err_happened.set(true);
-                        output.set_value(&format!("Error, {}", e));
-                        return;
-                    }
-                };
+        Callback::from(move |_: Event| {
+            if let Some(v) = &*download {
+                show_output.emit(Ok((**v).clone()));
+            }
+        })
+    };
 
-                let plain = textbox.value();
-                let cipher = encrypt(e, plain.chars().map(|c| c as _));
+    let save_download = {
+        let download = download.clone();
+        let output_encoding = output_encoding.clone();
 
-                err_happened.set(cipher.is_err());
-                match cipher {
-                    Ok(v) => output.set_value(&String::from_iter(v.into_iter().map(char::from))),
-                    Err(_) => output.set_value("Error, cannot encrypt!"),
-                }
+        Callback::from(move |_| {
+            let Some(v) = &*download else {
+                return;
+            };
+            let (name, data) = v.file(selected_encoding(&output_encoding));
+            if let Err(e) = save_file(&name, &data) {
+                web_sys::console::log_1(&e);
             }
         })
     };
 
-    let decrypt_ = {
+    let read_input = {
         let textbox = textbox.clone();
-        let output = output.clone();
-        let err_happened = err_happened.setter();
+        let input_encoding = input_encoding.clone();
 
-        let decryptor = props.decryptor.clone();
+        move || -> Result<Vec<u8>, AttrValue> {
+            let Some(textbox) = textbox.cast::<HtmlTextAreaElement>() else {
+                return Err(AttrValue::from("internal error"));
+            };
+            Ok(selected_encoding(&input_encoding).decode(&textbox.value())?)
+        }
+    };
+
+    let analyze_input = {
+        let read_input = read_input.clone();
+        let analyzed = analyzed.setter();
 
         Callback::from(move |_| {
-            if let (Some(textbox), Some(output)) = (
-                textbox.cast::<HtmlTextAreaElement>(),
-                output.cast::<HtmlTextAreaElement>(),
-            ) {
-                let d = match decryptor.emit(()) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        err_happened.set(true);
-                        output.set_value(&format!("Error, {}", e));
-                        return;
-                    }
-                };
+            if let Ok(v) = read_input() {
+                analyzed.set(Some(Rc::new(v)));
+            }
+        })
+    };
+    let analyze_output = {
+        let download = download.clone();
+        let analyzed = analyzed.setter();
+
+        Callback::from(move |_| {
+            if let Some(v) = &*download {
+                analyzed.set(Some(Rc::new(v.data.clone())));
+            }
+        })
+    };
 
-                let cipher = textbox.value();
-                let plain = decrypt(d, cipher.chars().map(|c| c as _));
+    let alphabet = {
+        let alphabet = props.alphabet.clone();
+        move || match &alphabet {
+            Some(v) => v.emit(()),
+            None => Ok(Alphabet::latin()),
+        }
+    };
 
-                err_happened.set(plain.is_err());
-                match plain {
-                    Ok(v) => output.set_value(&String::from_iter(v.into_iter().map(char::from))),
-                    Err(_) => output.set_value("Error, cannot decrypt!"),
+    let formatter = {
+        let layout_select = layout_select.clone();
+        let group_size = group_size.clone();
+        let group_per_line = group_per_line.clone();
+        let formatting = props.formatting;
+        let alphabet = alphabet.clone();
+
+        Callback::from(
+            move |e: Box<dyn Encryptor>| -> Result<Box<dyn Encryptor>, AttrValue> {
+                if !formatting {
+                    return Ok(e);
                 }
-            }
+
+                let alphabet = alphabet()?;
+                Ok(
+                    match output_layout(&layout_select, &group_size, &group_per_line)? {
+                        OutputLayout::Format(Layout::Grouped { .. }) if alphabet.contains(b' ') => {
+                            return Err(AttrValue::from(
+                                "grouping is ambiguous for alphabets with space, choose another output layout",
+                            ))
+                        }
+                        OutputLayout::Format(layout) => Box::new(e.format(layout)),
+                        OutputLayout::Preserve => Box::new(e.preserve(move |b| alphabet.contains(b))),
+                    },
+                )
+            },
+        )
+    };
+
+    let preserver = {
+        let layout_select = layout_select.clone();
+        let group_size = group_size.clone();
+        let group_per_line = group_per_line.clone();
+        let formatting = props.formatting;
+        let alphabet = alphabet.clone();
+
+        Callback::from(
+            move |d: Box<dyn Decryptor>| -> Result<Box<dyn Decryptor>, AttrValue> {
+                if !formatting {
+                    return Ok(d);
+                }
+
+                Ok(
+                    match output_layout(&layout_select, &group_size, &group_per_line)? {
+                        OutputLayout::Format(_) => d,
+                        OutputLayout::Preserve => {
+                            let alphabet = alphabet()?;
+                            Box::new(d.preserve(move |b| alphabet.contains(b)))
+                        }
+                    },
+                )
+            },
+        )
+    };
+
+    let encrypt_ = {
+        let read_input = read_input.clone();
+        let show_output = show_output.clone();
+
+        let encryptor = props.encryptor.clone();
+        let formatter = formatter.clone();
+        let show_steps = show_steps.clone();
+        let steps = steps.setter();
+
+        Callback::from(move |_| {
+            let tracing = is_checked(&show_steps);
+            steps.set(None);
+            show_output.emit((|| {
+                let mut e = encryptor.emit(()).and_then(|e| formatter.emit(e))?;
+                e.set_tracing(tracing);
+                let data = encrypt(&mut *e, read_input()?).map_err(|e| e.to_string())?;
+                if tracing {
+                    steps.set(Some(Rc::new(e.take_trace())));
+                }
+                Ok(Download {
+                    name: "ciphertext".into(),
+                    data,
+                    text: true,
+                })
+            })());
+        })
+    };
+
+    let decrypt_ = {
+        let read_input = read_input.clone();
+        let show_output = show_output.clone();
+
+        let decryptor = props.decryptor.clone();
+        let preserver = preserver.clone();
+        let show_steps = show_steps.clone();
+        let steps = steps.setter();
+
+        Callback::from(move |_| {
+            let tracing = is_checked(&show_steps);
+            steps.set(None);
+            show_output.emit((|| {
+                let mut d = decryptor.emit(()).and_then(|d| preserver.emit(d))?;
+                d.set_tracing(tracing);
+                let data = decrypt(&mut *d, read_input()?).map_err(|e| e.to_string())?;
+                if tracing {
+                    steps.set(Some(Rc::new(d.take_trace())));
+                }
+                Ok(Download {
+                    name: "plaintext".into(),
+                    data,
+                    text: true,
+                })
+            })());
         })
     };
 
@@ -187,11 +524,13 @@ pub fn cipher_box(props: &CipherBoxProps) -> Html {
     let execute_file = {
         let file_input = file_input.clone();
         let textbox = textbox.clone();
-        let output = output.clone();
-        let err_happened = err_happened.setter();
+        let input_encoding = input_encoding.clone();
+        let show_output = show_output.clone();
 
         let encryptor = props.encryptor.clone();
         let decryptor = props.decryptor.clone();
+        let formatter = formatter.clone();
+        let preserver = preserver.clone();
 
         let operator = operator.clone();
 
@@ -199,11 +538,12 @@ pub fn cipher_box(props: &CipherBoxProps) -> Html {
             move |_, operator| {
                 let operator = **operator;
 
-                let (Some(file_input), Some(textbox), Some(output)) = (
+                let (Some(file_input), Some(textbox)) = (
                     file_input.cast::<HtmlInputElement>(),
                     textbox.cast::<HtmlTextAreaElement>(),
-                    output.cast::<HtmlTextAreaElement>(),
-                ) else {return};
+                ) else {
+                    return;
+                };
 
                 let f = match file_input.files() {
                     Some(files) => match files.get(0) {
@@ -215,7 +555,10 @@ pub fn cipher_box(props: &CipherBoxProps) -> Html {
 
                 let encryptor = encryptor.clone();
                 let decryptor = decryptor.clone();
-                let err_happened = err_happened.clone();
+                let formatter = formatter.clone();
+                let preserver = preserver.clone();
+                let input_encoding = input_encoding.clone();
+                let show_output = show_output.clone();
 
                 spawn_local(async move {
                     let data = match JsFuture::from(f.array_buffer()).await {
@@ -226,39 +569,30 @@ pub fn cipher_box(props: &CipherBoxProps) -> Html {
                         }
                     };
 
-                    textbox.set_value(&String::from_iter(data.iter().map(|&b| b as char)));
-
-                    let out = match operator {
-                        Operator::Encrypt => encrypt(
-                            match encryptor.emit(()) {
-                                Ok(v) => v,
-                                Err(e) => {
-                                    err_happened.set(true);
-                                    output.set_value(&format!("Error, {}", e));
-                                    return;
-                                }
+                    textbox.set_value(&selected_encoding(&input_encoding).encode(&data).0);
+
+                    show_output.emit((|| {
+                        Ok(match operator {
+                            Operator::Encrypt => Download {
+                                name: encrypted_name(&f.name()),
+                                data: encrypt(
+                                    &mut *encryptor.emit(()).and_then(|e| formatter.emit(e))?,
+                                    data,
+                                )
+                                .map_err(|e| e.to_string())?,
+                                text: false,
                             },
-                            data.into_iter(),
-                        ),
-                        Operator::Decrypt => decrypt(
-                            match decryptor.emit(()) {
-                                Ok(v) => v,
-                                Err(e) => {
-                                    err_happened.set(true);
-                                    output.set_value(&format!("Error, {}", e));
-                                    return;
-                                }
+                            Operator::Decrypt => Download {
+                                name: decrypted_name(&f.name()),
+                                data: decrypt(
+                                    &mut *decryptor.emit(()).and_then(|d| preserver.emit(d))?,
+                                    data,
+                                )
+                                .map_err(|e| e.to_string())?,
+                                text: false,
                             },
-                            data.into_iter(),
-                        ),
-                    };
-
-                    err_happened.set(out.is_err());
-                    if let Ok(v) = out {
-                        output.set_value(&String::from_iter(v.iter().map(|&b| b as char)));
-                    } else {
-                        output.set_value("Error processing");
-                    }
+                        })
+                    })());
                 });
             },
             operator,
@@ -270,6 +604,17 @@ pub fn cipher_box(props: &CipherBoxProps) -> Html {
             <div class="key_container">
                 { for props.children.iter() }
             </div>
+            if props.key_view != Html::default() {
+                <div class="key_view">
+                    { props.key_view.clone() }
+                </div>
+            }
+            <div class="format_container">
+                <label> { "Input encoding:" } </label>
+                <EncodingSelect select={input_encoding} />
+                <label> { "Output encoding:" } </label>
+                <EncodingSelect select={output_encoding} onchange={reencode_output} />
+            </div>
             <textarea ref={textbox} cols=80 rows=10/>
             <textarea ref={output}
                 class={ classes!(if *err_happened { Some("error") } else { None } ) }
@@ -277,47 +622,119 @@ pub fn cipher_box(props: &CipherBoxProps) -> Html {
                 cols=80 rows=10
                 style="resize: none;"
             />
+            if *lossy {
+                <p class="warning">
+                    { "Output is not valid UTF-8 and is shown lossily, choose another encoding to copy it." }
+                </p>
+            }
+            if props.formatting {
+                <div class="format_container">
+                    <label> { "Output:" } </label>
+                    <select ref={layout_select}>
+                        <option value="grouped" selected=true> { "Groups" } </option>
+                        <option value="raw"> { "No spacing" } </option>
+                        <option value="preserve"> { "Keep non-letters in place" } </option>
+                    </select>
+                    <label> { "Group size:" } </label>
+                    <input ref={group_size} type="number" min="1" value="5" />
+                    <label> { "Groups per line:" } </label>
+                    <input ref={group_per_line} type="number" min="0" value="12" />
+                </div>
+            }
             <div class="action_container">
                 <button onclick={encrypt_}> { "Encrypt" } </button>
                 <button onclick={decrypt_}> { "Decrypt" } </button>
                 <button onclick={encrypt_file}> { "Encrypt File" } </button>
                 <button onclick={decrypt_file}> { "Decrypt File" } </button>
                 <input ref={file_input} type="file" hidden=true onchange={execute_file}/>
+                if download.is_some() {
+                    <button onclick={save_download}> { "Download Result" } </button>
+                }
+                <button onclick={analyze_input}> { "Analyze Input" } </button>
+                <button onclick={analyze_output}> { "Analyze Output" } </button>
+                <label>
+                    <input ref={show_steps} type="checkbox" />
+                    { "Show steps" }
+                </label>
             </div>
+            if let Some(steps) = (*steps).clone() {
+                <StepsTable { steps } />
+            }
+            if let Some(data) = (*analyzed).clone() {
+                <FrequencyPanel
+                    { data }
+                    symbols={ if props.formatting { Symbols::Letters } else { Symbols::Bytes } }
+                />
+            }
         </div>
     }
 }
 
 #[function_component(CipherVigenere)]
 pub fn cipher_vigenere() -> Html {
-    fn f(input: &NodeRef) -> Result<impl Encryptor + Decryptor, AttrValue> {
+    fn f(
+        input: &NodeRef,
+        alphabet: &AlphabetInput,
+    ) -> Result<impl Encryptor + Decryptor, AttrValue> {
         let key = if let Some(input) = input.cast::<HtmlInputElement>() {
             input.value()
         } else {
             return Err(AttrValue::from("internal error"));
         };
 
-        Ok(<_ as Encryptor>::filter(
-            Vignere::new(key.as_bytes())?,
-            |b| matches!(b as char, 'A'..='Z' | 'a'..='z'),
-        ))
+        let alphabet = alphabet.read()?;
+        let c = Vignere::with_alphabet(key.as_bytes(), alphabet.clone())?;
+
+        Ok(alphabet_filter(c, alphabet))
     }
 
     let input = use_node_ref();
+    let alphabet = AlphabetInput {
+        select: use_node_ref(),
+        symbols: use_node_ref(),
+    };
 
     let cb_e = {
         let input = input.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
     };
     let cb_d = {
         let input = input.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
+    };
+
+    let cb_a = {
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| alphabet.read())
+    };
+
+    let tabula = use_state_eq(Vec::<Vec<AttrValue>>::new);
+    let update_tabula = {
+        let input = input.clone();
+        let alphabet = alphabet.clone();
+        let tabula = tabula.setter();
+        Callback::from(move |()| {
+            let (Some(key), Ok(alphabet)) = (input.cast::<HtmlInputElement>(), alphabet.read())
+            else {
+                tabula.set(Vec::new());
+                return;
+            };
+            tabula.set(tabula_rows(&alphabet, key.value().as_bytes()));
+        })
+    };
+    let key_view = if tabula.len() > 1 {
+        html! { <KeyTable caption="Tabula recta rows of the key" rows={ (*tabula).clone() } headers=true /> }
+    } else {
+        Html::default()
     };
 
     html! {
-        <CipherBox encryptor={ cb_e } decryptor={ cb_d }>
+        <CipherBox encryptor={ cb_e } decryptor={ cb_d } alphabet={ cb_a } { key_view }>
             <label> { "Key:" } </label>
-            <input ref={ input } />
+            <input ref={ input } oninput={ update_tabula.reform(|_| ()) } />
+            <AlphabetSelect input={ alphabet } onchange={ update_tabula } />
         </CipherBox>
     }
 }
@@ -346,7 +763,7 @@ pub fn cipher_vigenere256() -> Html {
     };
 
     html! {
-        <CipherBox encryptor={ cb_e } decryptor={ cb_d }>
+        <CipherBox encryptor={ cb_e } decryptor={ cb_d } formatting={ false }>
             <label> { "Key:" } </label>
             <input ref={ input } />
         </CipherBox>
@@ -355,54 +772,353 @@ pub fn cipher_vigenere256() -> Html {
 
 #[function_component(CipherVigenereAutokey)]
 pub fn cipher_vigenere_autokey() -> Html {
-    fn f(input: &NodeRef) -> Result<impl Encryptor + Decryptor, AttrValue> {
+    fn f(
+        input: &NodeRef,
+        alphabet: &AlphabetInput,
+    ) -> Result<impl Encryptor + Decryptor, AttrValue> {
         let key = if let Some(input) = input.cast::<HtmlInputElement>() {
             input.value()
         } else {
             return Err(AttrValue::from("internal error"));
         };
 
-        Ok(<_ as Encryptor>::filter(
-            VignereAutokey::new(key.as_bytes())?,
-            |b| matches!(b as char, 'A'..='Z' | 'a'..='z'),
-        ))
+        let alphabet = alphabet.read()?;
+        let c = VignereAutokey::with_alphabet(key.as_bytes(), alphabet.clone())?;
+
+        Ok(alphabet_filter(c, alphabet))
     }
 
     let input = use_node_ref();
+    let alphabet = AlphabetInput {
+        select: use_node_ref(),
+        symbols: use_node_ref(),
+    };
 
     let cb_e = {
         let input = input.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
     };
     let cb_d = {
         let input = input.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
+    };
+
+    let cb_a = {
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| alphabet.read())
     };
 
     html! {
-        <CipherBox encryptor={ cb_e } decryptor={ cb_d }>
+        <CipherBox encryptor={ cb_e } decryptor={ cb_d } alphabet={ cb_a }>
             <label> { "Key:" } </label>
             <input ref={ input } />
+            <AlphabetSelect input={ alphabet } />
         </CipherBox>
     }
 }
 
-#[function_component(CipherPlayfair)]
-pub fn cipher_playfair() -> Html {
-    fn f(input: &NodeRef) -> Result<impl Encryptor + Decryptor, AttrValue> {
-        let key = if let Some(input) = input.cast::<HtmlInputElement>() {
+/// Passes only symbols of the alphabet into the cipher.
+fn alphabet_filter(
+    cipher: impl Encryptor + Decryptor,
+    alphabet: Alphabet,
+) -> impl Encryptor + Decryptor {
+    <_ as Encryptor>::filter(cipher, move |b| alphabet.contains(b))
+}
+
+/// Reads key input and autokey checkbox.
+fn key_autokey(input: &NodeRef, autokey: &NodeRef) -> Result<(String, bool), AttrValue> {
+    match (
+        input.cast::<HtmlInputElement>(),
+        autokey.cast::<HtmlInputElement>(),
+    ) {
+        (Some(input), Some(autokey)) => Ok((input.value(), autokey.checked())),
+        _ => Err(AttrValue::from("internal error")),
+    }
+}
+
+/// Periodic tableau cipher with an alphabet and optional autokey.
+pub trait PeriodicCipher: Encryptor + Decryptor + Sized + 'static {
+    fn with_alphabet(key: &[u8], alphabet: Alphabet, autokey: bool) -> Result<Self, &'static str>;
+}
+
+impl PeriodicCipher for Beaufort {
+    fn with_alphabet(key: &[u8], alphabet: Alphabet, autokey: bool) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, alphabet, autokey)
+    }
+}
+
+impl PeriodicCipher for VariantBeaufort {
+    fn with_alphabet(key: &[u8], alphabet: Alphabet, autokey: bool) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, alphabet, autokey)
+    }
+}
+
+impl PeriodicCipher for Gronsfeld {
+    fn with_alphabet(key: &[u8], alphabet: Alphabet, autokey: bool) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, alphabet, autokey)
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CipherPeriodicProps {
+    #[prop_or(AttrValue::Static("Key:"))]
+    pub key_label: AttrValue,
+    #[prop_or_default]
+    pub placeholder: AttrValue,
+}
+
+#[function_component(CipherPeriodic)]
+pub fn cipher_periodic<C: PeriodicCipher>(props: &CipherPeriodicProps) -> Html {
+    fn f<C: PeriodicCipher>(
+        input: &NodeRef,
+        autokey: &NodeRef,
+        alphabet: &AlphabetInput,
+    ) -> Result<impl Encryptor + Decryptor, AttrValue> {
+        let (key, autokey) = key_autokey(input, autokey)?;
+        let alphabet = alphabet.read()?;
+        let c = C::with_alphabet(key.as_bytes(), alphabet.clone(), autokey)?;
+
+        Ok(alphabet_filter(c, alphabet))
+    }
+
+    let input = use_node_ref();
+    let autokey = use_node_ref();
+    let alphabet = AlphabetInput {
+        select: use_node_ref(),
+        symbols: use_node_ref(),
+    };
+
+    let cb_e = {
+        let input = input.clone();
+        let autokey = autokey.clone();
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f::<C>(&input, &autokey, &alphabet)?) as _))
+    };
+    let cb_d = {
+        let input = input.clone();
+        let autokey = autokey.clone();
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f::<C>(&input, &autokey, &alphabet)?) as _))
+    };
+
+    let cb_a = {
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| alphabet.read())
+    };
+
+    html! {
+        <CipherBox encryptor={ cb_e } decryptor={ cb_d } alphabet={ cb_a }>
+            <label> { props.key_label.clone() } </label>
+            <input ref={ input } placeholder={ props.placeholder.clone() } />
+            <label> { "Ciphertext autokey:" } </label>
+            <input ref={ autokey } type="checkbox" />
+            <AlphabetSelect input={ alphabet } />
+        </CipherBox>
+    }
+}
+
+#[function_component(CipherRunningKey)]
+pub fn cipher_running_key() -> Html {
+    fn f(
+        input: &NodeRef,
+        alphabet: &AlphabetInput,
+    ) -> Result<impl Encryptor + Decryptor, AttrValue> {
+        let key = if let Some(input) = input.cast::<HtmlTextAreaElement>() {
             input.value()
         } else {
             return Err(AttrValue::from("internal error"));
         };
 
-        Ok(<_ as Encryptor>::filter(
-            Playfair::new(key.as_bytes())?,
-            |b| matches!(b as char, 'A'..='Z' | 'a'..='z'),
-        ))
+        let alphabet = alphabet.read()?;
+        let c = RunningKey::with_alphabet(key.into_bytes(), alphabet.clone());
+
+        Ok(alphabet_filter(c, alphabet))
     }
 
     let input = use_node_ref();
+    let alphabet = AlphabetInput {
+        select: use_node_ref(),
+        symbols: use_node_ref(),
+    };
+    let key_file = use_node_ref();
+
+    let load_key = {
+        let input = input.clone();
+        let key_file = key_file.clone();
+
+        Callback::from(move |_: Event| {
+            let (Some(input), Some(key_file)) = (
+                input.cast::<HtmlTextAreaElement>(),
+                key_file.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+            let Some(f) = key_file.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+
+            spawn_local(async move {
+                match JsFuture::from(f.array_buffer()).await {
+                    Ok(v) => {
+                        input.set_value(&String::from_utf8_lossy(&Uint8Array::new(&v).to_vec()))
+                    }
+                    Err(e) => web_sys::console::log_1(&e),
+                }
+            });
+        })
+    };
+
+    let cb_e = {
+        let input = input.clone();
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
+    };
+    let cb_d = {
+        let input = input.clone();
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
+    };
+
+    let cb_a = {
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| alphabet.read())
+    };
+
+    html! {
+        <CipherBox encryptor={ cb_e } decryptor={ cb_d } alphabet={ cb_a }>
+            <label> { "Key text:" } </label>
+            <textarea ref={ input } cols=80 rows=4 />
+            <label> { "Key file:" } </label>
+            <input ref={ key_file } type="file" onchange={ load_key } />
+            <AlphabetSelect input={ alphabet } />
+        </CipherBox>
+    }
+}
+
+/// Playfair inputs of the tab.
+#[derive(Debug, Clone, PartialEq)]
+struct PlayfairInput {
+    key: NodeRef,
+    grid: NodeRef,
+    symbols: NodeRef,
+    rows: NodeRef,
+    filler: NodeRef,
+    merge: NodeRef,
+    doubles: NodeRef,
+    strip: NodeRef,
+}
+
+impl PlayfairInput {
+    /// Cipher and whether fillers are removed.
+    fn read(&self) -> Result<(Playfair, bool), AttrValue> {
+        let (
+            Some(key),
+            Some(grid),
+            Some(symbols),
+            Some(rows),
+            Some(filler),
+            Some(merge),
+            Some(doubles),
+            Some(strip),
+        ) = (
+            self.key.cast::<HtmlInputElement>(),
+            self.grid.cast::<HtmlSelectElement>(),
+            self.symbols.cast::<HtmlInputElement>(),
+            self.rows.cast::<HtmlInputElement>(),
+            self.filler.cast::<HtmlInputElement>(),
+            self.merge.cast::<HtmlSelectElement>(),
+            self.doubles.cast::<HtmlSelectElement>(),
+            self.strip.cast::<HtmlInputElement>(),
+        )
+        else {
+            return Err(AttrValue::from("internal error"));
+        };
+
+        let filler = match filler.value().trim().as_bytes() {
+            &[b] => b,
+            _ => return Err(AttrValue::from("filler must be a single symbol")),
+        };
+        let merge = match &*merge.value() {
+            "q" => Merge::DropQ,
+            "ck" => Merge::CK,
+            _ => Merge::IJ,
+        };
+        let doubles = match &*doubles.value() {
+            "all" => Doubles::All,
+            "never" => Doubles::Never,
+            _ => Doubles::Pair,
+        };
+
+        let options = PlayfairOptions {
+            filler,
+            merge,
+            doubles,
+        };
+
+        let key = key.value();
+        let c = match &*grid.value() {
+            "6x6" => Playfair::with_grid(Grid::alphanumeric(key.as_bytes()), options)?,
+            "custom" => {
+                let Ok(rows) = rows.value().trim().parse() else {
+                    return Err(AttrValue::from("rows must be a number"));
+                };
+                let alphabet = Alphabet::new(symbols.value().as_bytes())?;
+                let grid = Grid::new(key.as_bytes(), alphabet, rows)?;
+                Playfair::with_grid(grid, options)?
+            }
+            _ => Playfair::with_options(key.as_bytes(), options)?,
+        };
+        Ok((c, strip.checked()))
+    }
+
+    /// Selects the 5×5 square with I and J merged, which keys from cryptanalysis are for.
+    fn select_solver_square(&self) {
+        if let Some(grid) = self.grid.cast::<HtmlSelectElement>() {
+            grid.set_value("5x5");
+        }
+        if let Some(merge) = self.merge.cast::<HtmlSelectElement>() {
+            merge.set_value("ij");
+        }
+    }
+
+    /// Symbols kept in place by the preserving layout.
+    fn alphabet(&self) -> Result<Alphabet, AttrValue> {
+        let (c, _) = self.read()?;
+        match self.grid.cast::<HtmlSelectElement>() {
+            // Merged letters are encrypted too
+            Some(grid) if grid.value() == "5x5" => Ok(Alphabet::latin()),
+            _ => Ok(Alphabet::new(c.grid().symbols())?),
+        }
+    }
+}
+
+#[function_component(CipherPlayfair)]
+pub fn cipher_playfair() -> Html {
+    fn f(input: &PlayfairInput) -> Result<impl Encryptor + Decryptor, AttrValue> {
+        let (c, strip) = input.read()?;
+        let accepts = c.clone();
+        let c: Box<dyn Cipher> = if strip {
+            let filler = c.filler();
+            Box::new(StripFiller::new(c, filler))
+        } else {
+            Box::new(c)
+        };
+
+        Ok(<_ as Encryptor>::filter(c, move |b| accepts.accepts(b)))
+    }
+
+    let input = PlayfairInput {
+        key: use_node_ref(),
+        grid: use_node_ref(),
+        symbols: use_node_ref(),
+        rows: use_node_ref(),
+        filler: use_node_ref(),
+        merge: use_node_ref(),
+        doubles: use_node_ref(),
+        strip: use_node_ref(),
+    };
 
     let cb_e = {
         let input = input.clone();
@@ -412,23 +1128,254 @@ pub fn cipher_playfair() -> Html {
         let input = input.clone();
         Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
     };
+    let cb_a = {
+        let input = input.clone();
+        Callback::from(move |()| input.alphabet())
+    };
+
+    let square = use_state_eq(|| Some(keysquare(&[], Merge::default())));
+    let update_square = {
+        let input = input.clone();
+        let square = square.setter();
+        Callback::from(move |()| square.set(input.read().ok().map(|(c, _)| c.grid().clone())))
+    };
+    let key_view = match &*square {
+        Some(grid) => html! { <KeyTable caption="Key square" rows={ grid_rows(grid) } /> },
+        None => Html::default(),
+    };
+
+    let on_use = {
+        let input = input.clone();
+        let update_square = update_square.clone();
+        Callback::from(move |k: AttrValue| {
+            if let Some(key) = input.key.cast::<HtmlInputElement>() {
+                input.select_solver_square();
+                key.set_value(&k);
+                update_square.emit(());
+            }
+        })
+    };
+    let on_input = update_square.reform(|_| ());
+    let on_change = update_square.reform(|_| ());
 
     html! {
-        <CipherBox encryptor={ cb_e } decryptor={ cb_d }>
-            <label> { "Key:" } </label>
-            <input ref={ input } />
+        <>
+            <CipherBox encryptor={ cb_e } decryptor={ cb_d } alphabet={ cb_a } { key_view }>
+                <label> { "Key:" } </label>
+                <input ref={ input.key } oninput={ on_input.clone() } />
+                <label> { "Grid:" } </label>
+                <select ref={ input.grid } onchange={ on_change.clone() }>
+                    <option value="5x5" selected=true> { "5×5 letters" } </option>
+                    <option value="6x6"> { "6×6 letters and digits" } </option>
+                    <option value="custom"> { "Custom" } </option>
+                </select>
+                <label> { "Custom symbols:" } </label>
+                <input
+                    ref={ input.symbols }
+                    placeholder="Used if grid is Custom"
+                    oninput={ on_input.clone() }
+                />
+                <label> { "Custom rows:" } </label>
+                <input ref={ input.rows } type="number" min="1" value="5" oninput={ on_input.clone() } />
+                <label> { "Filler:" } </label>
+                <input ref={ input.filler } value="X" maxlength="1" oninput={ on_input } />
+                <label> { "Merged letters:" } </label>
+                <select ref={ input.merge } onchange={ on_change }>
+                    <option value="ij" selected=true> { "I and J" } </option>
+                    <option value="q"> { "Drop Q" } </option>
+                    <option value="ck"> { "C and K" } </option>
+                </select>
+                <label> { "Split doubled letters:" } </label>
+                <select ref={ input.doubles }>
+                    <option value="pair" selected=true> { "Within a pair" } </option>
+                    <option value="all"> { "Everywhere, also across pairs" } </option>
+                    <option value="never"> { "Never" } </option>
+                </select>
+                <label> { "Remove fillers when decrypting:" } </label>
+                <input ref={ input.strip } type="checkbox" />
+            </CipherBox>
+            <PlayfairAnalysis { on_use } />
+        </>
+    }
+}
+
+/// Two-Square and Four-Square inputs of the tabs.
+#[derive(Debug, Clone, PartialEq)]
+struct SquaresInput {
+    upper: NodeRef,
+    lower: NodeRef,
+    merge: NodeRef,
+}
+
+impl SquaresInput {
+    fn read(&self) -> Result<(String, String, PlayfairOptions), AttrValue> {
+        let (Some(upper), Some(lower), Some(merge)) = (
+            self.upper.cast::<HtmlInputElement>(),
+            self.lower.cast::<HtmlInputElement>(),
+            self.merge.cast::<HtmlSelectElement>(),
+        ) else {
+            return Err(AttrValue::from("internal error"));
+        };
+
+        let merge = match &*merge.value() {
+            "q" => Merge::DropQ,
+            "ck" => Merge::CK,
+            _ => Merge::IJ,
+        };
+        let options = PlayfairOptions {
+            merge,
+            ..Default::default()
+        };
+        Ok((upper.value(), lower.value(), options))
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct SquaresFieldsProps {
+    input: SquaresInput,
+    /// Called when the keys may have changed.
+    onchange: Callback<()>,
+}
+
+#[function_component(SquaresFields)]
+fn squares_fields(props: &SquaresFieldsProps) -> Html {
+    html! {
+        <>
+            <label> { "Upper key:" } </label>
+            <input ref={ props.input.upper.clone() } oninput={ props.onchange.reform(|_| ()) } />
+            <label> { "Lower key:" } </label>
+            <input ref={ props.input.lower.clone() } oninput={ props.onchange.reform(|_| ()) } />
+            <label> { "Merged letters:" } </label>
+            <select ref={ props.input.merge.clone() } onchange={ props.onchange.reform(|_| ()) }>
+                <option value="ij" selected=true> { "I and J" } </option>
+                <option value="q"> { "Drop Q" } </option>
+                <option value="ck"> { "C and K" } </option>
+            </select>
+        </>
+    }
+}
+
+#[function_component(CipherTwoSquare)]
+pub fn cipher_two_square() -> Html {
+    fn f(input: &SquaresInput) -> Result<impl Encryptor + Decryptor, AttrValue> {
+        let (upper, lower, options) = input.read()?;
+        let c = TwoSquare::with_options(upper.as_bytes(), lower.as_bytes(), options)?;
+        let accepts = c.clone();
+
+        Ok(<_ as Encryptor>::filter(c, move |b| accepts.accepts(b)))
+    }
+
+    let input = SquaresInput {
+        upper: use_node_ref(),
+        lower: use_node_ref(),
+        merge: use_node_ref(),
+    };
+
+    let cb_e = {
+        let input = input.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+    };
+    let cb_d = {
+        let input = input.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+    };
+
+    let squares = use_state_eq(|| TwoSquare::new(&[], &[]));
+    let update_squares = {
+        let input = input.clone();
+        let squares = squares.setter();
+        Callback::from(move |()| {
+            let Ok((upper, lower, options)) = input.read() else {
+                return;
+            };
+            if let Ok(c) = TwoSquare::with_options(upper.as_bytes(), lower.as_bytes(), options) {
+                squares.set(c);
+            }
+        })
+    };
+    let key_view = html! {
+        <>
+            <KeyTable caption="Upper square" rows={ grid_rows(squares.upper()) } />
+            <KeyTable caption="Lower square" rows={ grid_rows(squares.lower()) } />
+        </>
+    };
+
+    html! {
+        <CipherBox encryptor={ cb_e } decryptor={ cb_d } { key_view }>
+            <SquaresFields { input } onchange={ update_squares } />
+        </CipherBox>
+    }
+}
+
+#[function_component(CipherFourSquare)]
+pub fn cipher_four_square() -> Html {
+    fn f(input: &SquaresInput) -> Result<impl Encryptor + Decryptor, AttrValue> {
+        let (upper, lower, options) = input.read()?;
+        let c = FourSquare::with_options(upper.as_bytes(), lower.as_bytes(), options)?;
+        let accepts = c.clone();
+
+        Ok(<_ as Encryptor>::filter(c, move |b| accepts.accepts(b)))
+    }
+
+    let input = SquaresInput {
+        upper: use_node_ref(),
+        lower: use_node_ref(),
+        merge: use_node_ref(),
+    };
+
+    let cb_e = {
+        let input = input.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+    };
+    let cb_d = {
+        let input = input.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+    };
+
+    let squares = use_state_eq(|| FourSquare::new(&[], &[]));
+    let update_squares = {
+        let input = input.clone();
+        let squares = squares.setter();
+        Callback::from(move |()| {
+            let Ok((upper, lower, options)) = input.read() else {
+                return;
+            };
+            if let Ok(c) = FourSquare::with_options(upper.as_bytes(), lower.as_bytes(), options) {
+                squares.set(c);
+            }
+        })
+    };
+    let key_view = html! {
+        <div class="four_square">
+            <KeyTable caption="Plaintext" rows={ grid_rows(squares.plain()) } />
+            <KeyTable caption="Upper key" rows={ grid_rows(squares.upper()) } />
+            <KeyTable caption="Lower key" rows={ grid_rows(squares.lower()) } />
+            <KeyTable caption="Plaintext" rows={ grid_rows(squares.plain()) } />
+        </div>
+    };
+
+    html! {
+        <CipherBox encryptor={ cb_e } decryptor={ cb_d } { key_view }>
+            <SquaresFields { input } onchange={ update_squares } />
         </CipherBox>
     }
 }
 
 #[function_component(CipherAffine)]
 pub fn cipher_affine() -> Html {
-    fn f(m: &NodeRef, n: &NodeRef) -> Result<impl Encryptor + Decryptor, AttrValue> {
+    fn f(
+        m: &NodeRef,
+        n: &NodeRef,
+        alphabet: &AlphabetInput,
+    ) -> Result<impl Encryptor + Decryptor, AttrValue> {
         let (m, n) = if let (Some(m), Some(n)) =
             (m.cast::<HtmlInputElement>(), n.cast::<HtmlInputElement>())
         {
-            match u8::from_str_radix(m.value().trim(), 10)
-                .and_then(|m| Ok((m, u8::from_str_radix(n.value().trim(), 10)?)))
+            match m
+                .value()
+                .trim()
+                .parse::<u32>()
+                .and_then(|m| Ok((m, n.value().trim().parse::<u32>()?)))
             {
                 Ok(v) => v,
                 Err(e) => return Err(<_>::from(e.to_string())),
@@ -437,39 +1384,129 @@ pub fn cipher_affine() -> Html {
             return Err(AttrValue::from("internal error"));
         };
 
-        Ok(<_ as Encryptor>::filter(
-            Affine::new(m, n)?,
-            |b| matches!(b as char, 'A'..='Z' | 'a'..='z'),
-        ))
+        let alphabet = alphabet.read()?;
+        let c = Affine::with_alphabet(m, n, alphabet.clone())?;
+
+        Ok(<_ as Encryptor>::filter(c, move |b| alphabet.contains(b)))
     }
 
     let input_m = use_node_ref();
     let input_n = use_node_ref();
+    let alphabet = AlphabetInput {
+        select: use_node_ref(),
+        symbols: use_node_ref(),
+    };
 
     let cb_e = {
         let input_m = input_m.clone();
         let input_n = input_n.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input_m, &input_n)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input_m, &input_n, &alphabet)?) as _))
     };
     let cb_d = {
         let input_m = input_m.clone();
         let input_n = input_n.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input_m, &input_n)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input_m, &input_n, &alphabet)?) as _))
+    };
+    let cb_a = {
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| alphabet.read())
+    };
+
+    let on_use = {
+        let input_m = input_m.clone();
+        let input_n = input_n.clone();
+        let alphabet = alphabet.clone();
+        Callback::from(move |(m, n): (u8, u8)| {
+            if let (Some(input_m), Some(input_n)) = (
+                input_m.cast::<HtmlInputElement>(),
+                input_n.cast::<HtmlInputElement>(),
+            ) {
+                input_m.set_value(&m.to_string());
+                input_n.set_value(&n.to_string());
+                alphabet.select_latin();
+            }
+        })
     };
 
     html! {
-        <CipherBox encryptor={ cb_e } decryptor={ cb_d }>
-            <label> { "M:" } </label>
-            <input ref={ input_m } type="number" min="1" max="25" value="1" />
-            <label> { "N:" } </label>
-            <input ref={ input_n } type="number" min="0" max="25" value="0" />
-        </CipherBox>
+        <>
+            <CipherBox encryptor={ cb_e } decryptor={ cb_d } alphabet={ cb_a }>
+                <label> { "M:" } </label>
+                <input ref={ input_m } type="number" min="1" value="1" />
+                <label> { "N:" } </label>
+                <input ref={ input_n } type="number" min="0" value="0" />
+                <AlphabetSelect input={ alphabet } />
+            </CipherBox>
+            <AffineAnalysis { on_use } />
+        </>
     }
 }
 
+/// Hill key matrix of the input, `None` if there is no key.
+fn hill_key(input: &NodeRef, alphabet: &AlphabetInput) -> Option<Result<MatrixZn, String>> {
+    let s = input.cast::<HtmlInputElement>()?.value();
+    if s.trim().is_empty() {
+        return None;
+    }
+
+    let Ok((_, key)) = list_u8(&s) else {
+        return Some(Err("Cannot convert key".into()));
+    };
+    let modulus = match alphabet.read() {
+        Ok(v) => v.len() as u32,
+        Err(e) => return Some(Err(format!("Error, {}", e))),
+    };
+    let Some(n) = (1..=key.len()).find(|n| n * n == key.len()) else {
+        return Some(Err(format!(
+            "{} entries are not a square matrix",
+            key.len()
+        )));
+    };
+
+    Some(Ok(MatrixZn::new(n, modulus, key.iter().map(|&v| v as _))))
+}
+
+/// Hill key matrix and its inverse, if any.
+fn hill_matrices(mat: MatrixZn) -> (MatrixZn, Option<MatrixZn>) {
+    let inv = mat.inverse().ok();
+    (mat, inv)
+}
+
+/// Determinant of the Hill key matrix and whether it is invertible.
+fn hill_key_info(key: &Result<MatrixZn, String>) -> (String, bool) {
+    let mat = match key {
+        Ok(v) => v,
+        Err(e) => return (e.clone(), false),
+    };
+
+    let (det, modulus) = (u32::from(mat.determinant()), mat.modulus());
+    let gcd = det.gcd(&modulus);
+    (
+        format!(
+            "Determinant: {} (mod {}), gcd({}, {}) = {}, {}",
+            det,
+            modulus,
+            det,
+            modulus,
+            gcd,
+            if gcd == 1 {
+                "invertible"
+            } else {
+                "not invertible"
+            }
+        ),
+        gcd == 1,
+    )
+}
+
 #[function_component(CipherHill)]
 pub fn cipher_hill() -> Html {
-    fn f(input: &NodeRef) -> Result<impl Encryptor + Decryptor, AttrValue> {
+    fn f(
+        input: &NodeRef,
+        alphabet: &AlphabetInput,
+    ) -> Result<impl Encryptor + Decryptor, AttrValue> {
         let key;
         if let Some(input) = input.cast::<HtmlInputElement>() {
             let s = input.value();
@@ -483,28 +1520,88 @@ pub fn cipher_hill() -> Html {
             return Err(AttrValue::from("internal error"));
         }
 
-        Ok(<_ as Encryptor>::filter(
-            Hill::new(&key)?,
-            |b| matches!(b as char, 'A'..='Z' | 'a'..='z'),
-        ))
+        let alphabet = alphabet.read()?;
+        let c = Hill::with_alphabet(&key, alphabet.clone())?;
+
+        Ok(<_ as Encryptor>::filter(c, move |b| alphabet.contains(b)))
     }
 
     let input = use_node_ref();
+    let alphabet = AlphabetInput {
+        select: use_node_ref(),
+        symbols: use_node_ref(),
+    };
 
     let cb_e = {
         let input = input.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
     };
     let cb_d = {
         let input = input.clone();
-        Callback::from(move |()| Ok(Box::new(f(&input)?) as _))
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| Ok(Box::new(f(&input, &alphabet)?) as _))
+    };
+    let cb_a = {
+        let alphabet = alphabet.clone();
+        Callback::from(move |()| alphabet.read())
+    };
+
+    let key_info = use_state_eq(|| None::<(String, bool)>);
+    let matrices = use_state_eq(|| None::<(MatrixZn, Option<MatrixZn>)>);
+    let update_info = {
+        let input = input.clone();
+        let alphabet = alphabet.clone();
+        let key_info = key_info.setter();
+        let matrices = matrices.setter();
+        Callback::from(move |()| {
+            let key = hill_key(&input, &alphabet);
+            key_info.set(key.as_ref().map(hill_key_info));
+            matrices.set(key.and_then(Result::ok).map(hill_matrices));
+        })
+    };
+    let key_view = match &*matrices {
+        Some((mat, inv)) => html! {
+            <>
+                <KeyTable caption="Key matrix" rows={ matrix_rows(mat) } />
+                if let Some(inv) = inv {
+                    <KeyTable caption="Inverse matrix" rows={ matrix_rows(inv) } />
+                }
+            </>
+        },
+        None => Html::default(),
+    };
+
+    let on_use = {
+        let input = input.clone();
+        let alphabet = alphabet.clone();
+        let update_info = update_info.clone();
+        Callback::from(move |key: AttrValue| {
+            if let Some(input) = input.cast::<HtmlInputElement>() {
+                input.set_value(&key);
+                alphabet.select_latin();
+                update_info.emit(());
+            }
+        })
     };
 
     html! {
-        <CipherBox encryptor={ cb_e } decryptor={ cb_d }>
-            <label> { "Square Matrix:" } </label>
-            <input ref={ input } />
-            <label style="grid-column: 1 / -1;"> { "Eg: 17 17 5 21 18 21 2 2 19" } </label>
-        </CipherBox>
+        <>
+            <CipherBox encryptor={ cb_e } decryptor={ cb_d } alphabet={ cb_a } { key_view }>
+                <label> { "Square Matrix:" } </label>
+                <input ref={ input } oninput={ update_info.reform(|_| ()) } />
+                <label style="grid-column: 1 / -1;"> { "Eg: 17 17 5 21 18 21 2 2 19" } </label>
+                <AlphabetSelect input={ alphabet } onchange={ update_info } />
+                if let Some((info, invertible)) = (*key_info).clone() {
+                    <label
+                        style="grid-column: 1 / -1;"
+                        class={ classes!(if invertible { None } else { Some("error") }) }
+                    >
+                        { info }
+                    </label>
+                }
+            </CipherBox>
+            <HillAnalysis { on_use } />
+        </>
     }
 }
@@ -0,0 +1,191 @@
+use std::rc::Rc;
+
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+use tugas_kripto_1a::analysis::frequency::{Frequency, Symbols};
+use tugas_kripto_1a::analysis::Language;
+
+const TOP_NGRAMS: usize = 10;
+
+#[derive(Properties, PartialEq)]
+pub struct FrequencyPanelProps {
+    pub data: Rc<Vec<u8>>,
+    #[prop_or_default]
+    pub symbols: Symbols,
+}
+
+fn symbol_name(b: u8, symbols: Symbols) -> String {
+    match symbols {
+        Symbols::Letters => char::from(b).to_string(),
+        Symbols::Bytes if b.is_ascii_graphic() => char::from(b).to_string(),
+        Symbols::Bytes => format!("{:02X}", b),
+    }
+}
+
+fn ngram_name(v: &[u8], symbols: Symbols) -> String {
+    match symbols {
+        Symbols::Letters => String::from_iter(v.iter().map(|&b| char::from(b))),
+        Symbols::Bytes => v
+            .iter()
+            .map(|&b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+fn bar(value: f64, max: f64, class: &'static str) -> Html {
+    let width = if max > 0. { value / max * 100. } else { 0. };
+    html! {
+        <div class={ classes!("bar", class) } style={ format!("width: {:.2}%;", width) } />
+    }
+}
+
+#[function_component(FrequencyPanel)]
+pub fn frequency_panel(props: &FrequencyPanelProps) -> Html {
+    let symbols = use_state_eq(|| props.symbols);
+    let language = use_state_eq(Language::default);
+
+    let on_symbols = {
+        let symbols = symbols.setter();
+        Callback::from(move |e: Event| {
+            let s = e.target_unchecked_into::<HtmlSelectElement>();
+            symbols.set(match &*s.value() {
+                "bytes" => Symbols::Bytes,
+                _ => Symbols::Letters,
+            });
+        })
+    };
+    let on_language = {
+        let language = language.setter();
+        Callback::from(move |e: Event| {
+            let s = e.target_unchecked_into::<HtmlSelectElement>();
+            let l = s.value().parse::<usize>().ok();
+            language.set(
+                l.and_then(|i| Language::ALL.get(i).copied())
+                    .unwrap_or_default(),
+            );
+        })
+    };
+
+    let freq = Frequency::new(&props.data, *symbols, TOP_NGRAMS);
+
+    // Reference profile: language for letters, uniform for bytes
+    let reference: Vec<f64> = match *symbols {
+        Symbols::Letters => {
+            let f = language.frequencies();
+            let total: f64 = f.iter().sum();
+            f.iter().map(|v| v / total).collect()
+        }
+        Symbols::Bytes => vec![1. / 256.; 256],
+    };
+    let ref_ioc = match *symbols {
+        Symbols::Letters => language.index_of_coincidence(),
+        Symbols::Bytes => 1. / 256.,
+    };
+
+    let chart = match *symbols {
+        Symbols::Letters => {
+            let max = (0..26)
+                .map(|i| freq.relative(i).max(reference[i]))
+                .fold(0., f64::max);
+
+            html! {
+                <table class="frequency_chart">
+                    <tr>
+                        <th />
+                        <th> { "Frequency" } </th>
+                        <th> { "Observed" } </th>
+                        <th> { language.name() } </th>
+                    </tr>
+                    { for (0..26).map(|i| html! {
+                        <tr>
+                            <td> { symbol_name(i as u8 + b'A', Symbols::Letters) } </td>
+                            <td class="bars">
+                                { bar(freq.relative(i), max, "observed") }
+                                { bar(reference[i], max, "reference") }
+                            </td>
+                            <td> { format!("{:.2}%", freq.relative(i) * 100.) } </td>
+                            <td> { format!("{:.2}%", reference[i] * 100.) } </td>
+                        </tr>
+                    }) }
+                </table>
+            }
+        }
+        Symbols::Bytes => {
+            let max = (0..256)
+                .map(|i| freq.relative(i).max(reference[i]))
+                .fold(0., f64::max);
+
+            html! {
+                <div class="histogram">
+                    { for (0..256).map(|i| {
+                        let height = if max > 0. { freq.relative(i) / max * 100. } else { 0. };
+                        html! {
+                            <div
+                                class="column"
+                                style={ format!("height: {:.2}%;", height) }
+                                title={ format!("{:02X}: {}", i, freq.monograms[i]) }
+                            />
+                        }
+                    }) }
+                </div>
+            }
+        }
+    };
+
+    let ngrams = |title: &'static str, v: &[(Vec<u8>, usize)]| {
+        html! {
+            <table class="analysis_table">
+                <tr> <th colspan="2"> { title } </th> </tr>
+                { for v.iter().map(|(k, c)| html! {
+                    <tr>
+                        <td class="key"> { ngram_name(k, *symbols) } </td>
+                        <td> { c } </td>
+                    </tr>
+                }) }
+            </table>
+        }
+    };
+
+    html! {
+        <div class="frequency_panel">
+            <div class="key_container">
+                <label> { "Symbols:" } </label>
+                <select onchange={ on_symbols }>
+                    <option value="letters" selected={ *symbols == Symbols::Letters }>
+                        { "Letters (A-Z)" }
+                    </option>
+                    <option value="bytes" selected={ *symbols == Symbols::Bytes }>
+                        { "Bytes" }
+                    </option>
+                </select>
+                if *symbols == Symbols::Letters {
+                    <label> { "Reference:" } </label>
+                    <select onchange={ on_language }>
+                        { for Language::ALL.iter().enumerate().map(|(i, l)| html! {
+                            <option value={ i.to_string() } selected={ l == &*language }>
+                                { l.name() }
+                            </option>
+                        }) }
+                    </select>
+                }
+            </div>
+            <p>
+                { format!(
+                    "Total: {}, Index of coincidence: {:.4} (reference {:.4}), Entropy: {:.3} bits (max {:.3})",
+                    freq.total,
+                    freq.ioc,
+                    ref_ioc,
+                    freq.entropy,
+                    (symbols.size() as f64).log2(),
+                ) }
+            </p>
+            { chart }
+            <div class="ngrams">
+                { ngrams("Bigrams", &freq.bigrams) }
+                { ngrams("Trigrams", &freq.trigrams) }
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,130 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use tugas_kripto_1a::ciphers::{Alphabet, Grid, TraceStep};
+use tugas_kripto_1a::util::MatrixZn;
+
+/// Printable form of a key symbol.
+fn symbol_text(b: u8) -> AttrValue {
+    match b {
+        b' ' => AttrValue::from("␣"),
+        0x21..=0x7E => AttrValue::from((b as char).to_string()),
+        _ => AttrValue::from(format!("{:02X}", b)),
+    }
+}
+
+/// Cells of a grid, row by row.
+pub fn grid_rows(grid: &Grid) -> Vec<Vec<AttrValue>> {
+    grid.symbols()
+        .chunks(grid.cols())
+        .map(|r| r.iter().map(|&b| symbol_text(b)).collect())
+        .collect()
+}
+
+/// Entries of a matrix, row by row.
+pub fn matrix_rows(mat: &MatrixZn) -> Vec<Vec<AttrValue>> {
+    mat.as_slice()
+        .chunks(mat.size())
+        .map(|r| {
+            r.iter()
+                .map(|&v| AttrValue::from(u32::from(v).to_string()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Tabula recta rows of the key symbols, in key order without repeats.
+///
+/// The first row is the plaintext alphabet, every row starts with its key symbol.
+pub fn tabula_rows(alphabet: &Alphabet, key: &[u8]) -> Vec<Vec<AttrValue>> {
+    let n = alphabet.len();
+    let mut used = vec![false; n];
+
+    let header = Some(AttrValue::default())
+        .into_iter()
+        .chain(alphabet.symbols().iter().map(|&b| symbol_text(b)));
+    let rows = key
+        .iter()
+        .filter_map(|&b| alphabet.index(b))
+        .filter(|&i| !std::mem::replace(&mut used[i as usize], true))
+        .map(|i| {
+            Some(symbol_text(alphabet.symbol(i)))
+                .into_iter()
+                .chain((0..n).map(|j| symbol_text(alphabet.symbol(((i as usize + j) % n) as u8))))
+                .collect()
+        });
+
+    Some(header.collect()).into_iter().chain(rows).collect()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct KeyTableProps {
+    pub caption: AttrValue,
+    pub rows: Vec<Vec<AttrValue>>,
+    /// First row and column are headers.
+    #[prop_or(false)]
+    pub headers: bool,
+}
+
+#[function_component(KeyTable)]
+pub fn key_table(props: &KeyTableProps) -> Html {
+    let cell = |i: usize, j: usize, v: &AttrValue| {
+        if props.headers && (i == 0 || j == 0) {
+            html! { <th> { v.clone() } </th> }
+        } else {
+            html! { <td> { v.clone() } </td> }
+        }
+    };
+
+    html! {
+        <table class="key_table">
+            <caption> { props.caption.clone() } </caption>
+            { for props.rows.iter().enumerate().map(|(i, r)| html! {
+                <tr> { for r.iter().enumerate().map(|(j, v)| cell(i, j, v)) } </tr>
+            }) }
+        </table>
+    }
+}
+
+/// Steps shown at most, a long text has a step per symbol.
+const MAX_STEPS: usize = 1000;
+
+#[derive(Properties, PartialEq)]
+pub struct StepsTableProps {
+    pub steps: Rc<Vec<TraceStep>>,
+}
+
+#[function_component(StepsTable)]
+pub fn steps_table(props: &StepsTableProps) -> Html {
+    let steps = &props.steps;
+    if steps.is_empty() {
+        return html! {
+            <p> { "No steps recorded, this cipher has no step trace." } </p>
+        };
+    }
+
+    html! {
+        <>
+            if steps.len() > MAX_STEPS {
+                <p> { format!("Showing the first {} of {} steps.", MAX_STEPS, steps.len()) } </p>
+            }
+            <table class="key_table steps_table">
+                <tr>
+                    <th> { "#" } </th>
+                    <th> { "Input" } </th>
+                    <th> { "Output" } </th>
+                    <th> { "Step" } </th>
+                </tr>
+                { for steps.iter().take(MAX_STEPS).enumerate().map(|(i, s)| html! {
+                    <tr>
+                        <td> { i + 1 } </td>
+                        <td> { s.input.escape_ascii().to_string() } </td>
+                        <td> { s.output.escape_ascii().to_string() } </td>
+                        <td> { s.detail.clone() } </td>
+                    </tr>
+                }) }
+            </table>
+        </>
+    }
+}
@@ -1,7 +1,13 @@
+mod comp_analysis;
 mod comp_ciphers;
+mod comp_frequency;
+mod comp_keyview;
 
 use yew::prelude::*;
 
+use tugas_kripto_1a::ciphers::{Beaufort, Gronsfeld, VariantBeaufort};
+
+use comp_analysis::*;
 use comp_ciphers::*;
 
 #[derive(Properties, PartialEq)]
@@ -65,9 +71,16 @@ pub fn app() -> Html {
         "Vigenere",
         "Vigenere (Autokey)",
         "Vigenere (8-bit)",
+        "Beaufort",
+        "Variant Beaufort",
+        "Gronsfeld",
+        "Running Key",
         "Playfair",
+        "Two-Square",
+        "Four-Square",
         "Affine",
         "Hill",
+        "Analyze",
     ]
     .into_iter()
     .map(AttrValue::from)
@@ -82,13 +95,34 @@ pub fn app() -> Html {
                 <CipherVigenere256 key={ 2 } />
             },
             Some(3) => html! {
-                <CipherPlayfair key={ 3 } />
+                <CipherPeriodic<Beaufort> key={ 3 } />
             },
             Some(4) => html! {
-                <CipherAffine key={ 4 } />
+                <CipherPeriodic<VariantBeaufort> key={ 4 } />
             },
             Some(5) => html! {
-                <CipherHill key={ 5 } />
+                <CipherPeriodic<Gronsfeld> key={ 5 } key_label="Key (digits):" placeholder="31415" />
+            },
+            Some(6) => html! {
+                <CipherRunningKey key={ 6 } />
+            },
+            Some(7) => html! {
+                <CipherPlayfair key={ 7 } />
+            },
+            Some(8) => html! {
+                <CipherTwoSquare key={ 8 } />
+            },
+            Some(9) => html! {
+                <CipherFourSquare key={ 9 } />
+            },
+            Some(10) => html! {
+                <CipherAffine key={ 10 } />
+            },
+            Some(11) => html! {
+                <CipherHill key={ 11 } />
+            },
+            Some(12) => html! {
+                <VigenereAnalysis key={ 12 } />
             },
             _ => html! {
                 <CipherVigenere key={ 0 } />
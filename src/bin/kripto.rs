@@ -0,0 +1,441 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+use tugas_kripto_1a::ciphers::*;
+use tugas_kripto_1a::parsers::list_u8;
+
+const USAGE: &str = "\
+Usage: kripto <encrypt|decrypt> <CIPHER> [OPTIONS] [INPUT]
+
+Ciphers:
+    vigenere            Vigenere cipher (--key, --alphabet)
+    vigenere-autokey    Autokey Vigenere cipher (--key, --alphabet)
+    vigenere-8bit       Vigenere cipher over bytes (--key)
+    beaufort            Beaufort cipher (--key, --autokey, --alphabet)
+    variant-beaufort    Variant Beaufort cipher (--key, --autokey, --alphabet)
+    gronsfeld           Gronsfeld cipher, numeric key (--key, --autokey, --alphabet)
+    running-key         Running key cipher (--key or --key-file, --alphabet)
+    playfair            Playfair cipher (--key, --grid, --filler, --merge, --doubles,
+                        --strip-filler, or --symbols with --rows)
+    two-square          Two-Square cipher, vertical (--key, --key2, --filler, --merge)
+    four-square         Four-Square cipher (--key, --key2, --filler, --merge)
+    affine              Affine cipher (-m, -n, --alphabet)
+    hill                Hill cipher (--matrix, --alphabet)
+
+Options:
+    -k, --key <KEY>         Cipher key
+    --key2 <KEY>            Second key of Two-Square and Four-Square (lower square)
+    --autokey               Extend key with ciphertext
+    --key-file <FILE>       Read cipher key from a file
+    -m <M>                  Affine multiplier
+    -n <N>                  Affine offset
+    --matrix <MATRIX>       Hill square matrix (eg: \"17 17 5 21 18 21 2 2 19\")
+    --grid <GRID>           Playfair grid: 5x5 (default, letters), 6x6 (letters and digits)
+    --rows <N>              Playfair grid rows for --symbols
+    --filler <SYMBOL>       Playfair and square ciphers filler (default: X)
+    --merge <MERGE>         Merged letters of 5x5 squares: ij (default), q (drop Q), ck
+    --doubles <DOUBLES>     Playfair doubled letter splitting: pair (default), all, never
+    --strip-filler          Remove Playfair fillers when decrypting
+    --alphabet <ALPHABET>   Cipher alphabet: latin (default), latin-digits,
+                            latin-digits-space, printable
+    --symbols <SYMBOLS>     Custom cipher alphabet, overrides --alphabet
+    --layout <LAYOUT>       Encrypted output layout: grouped (default), raw, preserve
+                            (preserve keeps non-letters in place, also when decrypting)
+    --group-size <N>        Letters per group (default: 5)
+    --groups-per-line <N>   Groups per line, 0 for single line (default: 12)
+    --encoding <ENCODING>   Ciphertext encoding: hex, base32, base64
+                            (encoded after encrypting, decoded before decrypting)
+    -o, --output <FILE>     Output file (default: stdout)
+    -h, --help              Print this help
+
+INPUT is a file name, or - for stdin (default).";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Encrypt,
+    Decrypt,
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    operator: Option<Operator>,
+    cipher: Option<String>,
+
+    key: Option<String>,
+    key2: Option<String>,
+    autokey: bool,
+    key_file: Option<String>,
+    m: Option<String>,
+    n: Option<String>,
+    matrix: Option<String>,
+    alphabet: Option<String>,
+    grid: Option<String>,
+    rows: Option<String>,
+    filler: Option<String>,
+    merge: Option<String>,
+    doubles: Option<String>,
+    strip_filler: bool,
+    symbols: Option<String>,
+
+    layout: Option<String>,
+    group_size: Option<String>,
+    groups_per_line: Option<String>,
+    encoding: Option<String>,
+
+    input: Option<String>,
+    output: Option<String>,
+}
+
+enum Parsed {
+    Run(Box<Args>),
+    Help,
+}
+
+fn parse_args(mut it: impl Iterator<Item = String>) -> Result<Parsed, String> {
+    let mut args = Args::default();
+
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| {
+            it.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+
+        match &*arg {
+            "-h" | "--help" => return Ok(Parsed::Help),
+            "-k" | "--key" => args.key = Some(value(&arg)?),
+            "--key2" => args.key2 = Some(value(&arg)?),
+            "--autokey" => args.autokey = true,
+            "--key-file" => args.key_file = Some(value(&arg)?),
+            "-m" => args.m = Some(value(&arg)?),
+            "-n" => args.n = Some(value(&arg)?),
+            "--matrix" => args.matrix = Some(value(&arg)?),
+            "--alphabet" => args.alphabet = Some(value(&arg)?),
+            "--grid" => args.grid = Some(value(&arg)?),
+            "--rows" => args.rows = Some(value(&arg)?),
+            "--filler" => args.filler = Some(value(&arg)?),
+            "--merge" => args.merge = Some(value(&arg)?),
+            "--doubles" => args.doubles = Some(value(&arg)?),
+            "--strip-filler" => args.strip_filler = true,
+            "--symbols" => args.symbols = Some(value(&arg)?),
+            "--layout" => args.layout = Some(value(&arg)?),
+            "--group-size" => args.group_size = Some(value(&arg)?),
+            "--groups-per-line" => args.groups_per_line = Some(value(&arg)?),
+            "--encoding" => args.encoding = Some(value(&arg)?),
+            "-o" | "--output" => args.output = Some(value(&arg)?),
+            s if s.starts_with('-') && s != "-" => return Err(format!("unknown option {}", s)),
+            _ if args.operator.is_none() => {
+                args.operator = Some(match &*arg {
+                    "encrypt" => Operator::Encrypt,
+                    "decrypt" => Operator::Decrypt,
+                    s => return Err(format!("unknown command {}", s)),
+                })
+            }
+            _ if args.cipher.is_none() => args.cipher = Some(arg),
+            _ if args.input.is_none() => args.input = Some(arg),
+            s => return Err(format!("unexpected argument {}", s)),
+        }
+    }
+
+    Ok(Parsed::Run(Box::new(args)))
+}
+
+trait Cipher: Encryptor + Decryptor {}
+
+impl<T: Encryptor + Decryptor> Cipher for T {}
+
+fn required<'a>(v: &'a Option<String>, name: &str) -> Result<&'a str, String> {
+    v.as_deref()
+        .ok_or_else(|| format!("{} is required for this cipher", name))
+}
+
+fn alphabet(args: &Args) -> Result<Alphabet, String> {
+    if let Some(symbols) = &args.symbols {
+        return Alphabet::new(symbols.as_bytes());
+    }
+
+    if args.grid.as_deref() == Some("6x6") {
+        return Alphabet::new(Alphabet::PRESETS[1].1);
+    }
+    let i = match args.alphabet.as_deref() {
+        None | Some("latin") => 0,
+        Some("latin-digits") => 1,
+        Some("latin-digits-space") => 2,
+        Some("printable") => 3,
+        Some(s) => return Err(format!("unknown alphabet {}", s)),
+    };
+    Alphabet::new(Alphabet::PRESETS[i].1)
+}
+
+fn merge(args: &Args) -> Result<Merge, String> {
+    Ok(match args.merge.as_deref() {
+        None | Some("ij") => Merge::IJ,
+        Some("q") => Merge::DropQ,
+        Some("ck") => Merge::CK,
+        Some(s) => return Err(format!("unknown merge {}", s)),
+    })
+}
+
+fn playfair_options(args: &Args) -> Result<PlayfairOptions, String> {
+    let mut options = PlayfairOptions::default();
+    if let Some(filler) = &args.filler {
+        options.filler = match filler.as_bytes() {
+            &[b] => b,
+            _ => return Err(String::from("filler must be a single symbol")),
+        };
+    }
+    options.merge = merge(args)?;
+    options.doubles = match args.doubles.as_deref() {
+        None | Some("pair") => Doubles::Pair,
+        Some("all") => Doubles::All,
+        Some("never") => Doubles::Never,
+        Some(s) => return Err(format!("unknown doubles {}", s)),
+    };
+    Ok(options)
+}
+
+fn playfair(args: &Args) -> Result<Playfair, String> {
+    let key = required(&args.key, "--key")?;
+    let options = playfair_options(args)?;
+    if let Some(symbols) = &args.symbols {
+        let rows = required(&args.rows, "--rows")?;
+        let rows = rows.trim().parse::<usize>().map_err(|e| e.to_string())?;
+        let grid = Grid::new(key.as_bytes(), Alphabet::new(symbols.as_bytes())?, rows)?;
+        return Ok(Playfair::with_grid(grid, options)?);
+    }
+
+    Ok(match args.grid.as_deref() {
+        None | Some("5x5") => Playfair::with_options(key.as_bytes(), options)?,
+        Some("6x6") => Playfair::with_grid(Grid::alphanumeric(key.as_bytes()), options)?,
+        Some(s) => return Err(format!("unknown grid {}", s)),
+    })
+}
+
+/// Bytes encrypted by the cipher, other bytes are skipped or kept in place by the layout.
+type Accepts = Box<dyn Fn(u8) -> bool>;
+
+fn build_cipher(args: &Args) -> Result<(Box<dyn Cipher>, Accepts), String> {
+    let cipher = required(&args.cipher, "CIPHER")?;
+    let alphabet = alphabet(args)?;
+    let a = alphabet.clone();
+    let in_alphabet: Accepts = Box::new(move |b| a.contains(b));
+
+    Ok(match cipher {
+        "vigenere" => {
+            let key = required(&args.key, "--key")?;
+            let c = Vignere::with_alphabet(key.as_bytes(), alphabet)?;
+            (Box::new(c), in_alphabet)
+        }
+        "vigenere-autokey" => {
+            let key = required(&args.key, "--key")?;
+            let c = VignereAutokey::with_alphabet(key.as_bytes(), alphabet)?;
+            (Box::new(c), in_alphabet)
+        }
+        "vigenere-8bit" => {
+            let key = required(&args.key, "--key")?;
+            (
+                Box::new(Vignere256::new(key.as_bytes())?),
+                Box::new(|_| true),
+            )
+        }
+        "beaufort" => {
+            let key = required(&args.key, "--key")?;
+            let c = Beaufort::with_alphabet(key.as_bytes(), alphabet, args.autokey)?;
+            (Box::new(c), in_alphabet)
+        }
+        "variant-beaufort" => {
+            let key = required(&args.key, "--key")?;
+            let c = VariantBeaufort::with_alphabet(key.as_bytes(), alphabet, args.autokey)?;
+            (Box::new(c), in_alphabet)
+        }
+        "gronsfeld" => {
+            let key = required(&args.key, "--key")?;
+            let c = Gronsfeld::with_alphabet(key.as_bytes(), alphabet, args.autokey)?;
+            (Box::new(c), in_alphabet)
+        }
+        "running-key" => {
+            let key = match (&args.key, &args.key_file) {
+                (_, Some(path)) => {
+                    std::fs::read(path).map_err(|e| format!("cannot open {}: {}", path, e))?
+                }
+                (Some(key), None) => key.clone().into_bytes(),
+                (None, None) => {
+                    return Err(String::from(
+                        "--key or --key-file is required for this cipher",
+                    ))
+                }
+            };
+            (
+                Box::new(RunningKey::with_alphabet(key, alphabet)),
+                in_alphabet,
+            )
+        }
+        "playfair" => {
+            let c = playfair(args)?;
+            let accepts = c.clone();
+            let accepts: Accepts = Box::new(move |b| accepts.accepts(b));
+            if args.strip_filler {
+                let filler = c.filler();
+                (Box::new(StripFiller::new(c, filler)), accepts)
+            } else {
+                (Box::new(c), accepts)
+            }
+        }
+        "two-square" => {
+            let upper = required(&args.key, "--key")?;
+            let lower = required(&args.key2, "--key2")?;
+            let options = playfair_options(args)?;
+            let c = TwoSquare::with_options(upper.as_bytes(), lower.as_bytes(), options)?;
+            let accepts = c.clone();
+            (Box::new(c), Box::new(move |b| accepts.accepts(b)))
+        }
+        "four-square" => {
+            let upper = required(&args.key, "--key")?;
+            let lower = required(&args.key2, "--key2")?;
+            let options = playfair_options(args)?;
+            let c = FourSquare::with_options(upper.as_bytes(), lower.as_bytes(), options)?;
+            let accepts = c.clone();
+            (Box::new(c), Box::new(move |b| accepts.accepts(b)))
+        }
+        "affine" => {
+            let m = required(&args.m, "-m")?;
+            let n = required(&args.n, "-n")?;
+            let m = m.trim().parse::<u32>().map_err(|e| e.to_string())?;
+            let n = n.trim().parse::<u32>().map_err(|e| e.to_string())?;
+            (
+                Box::new(Affine::with_alphabet(m, n, alphabet)?),
+                in_alphabet,
+            )
+        }
+        "hill" => {
+            let mat = required(&args.matrix, "--matrix")?;
+            let (_, mat) = list_u8(mat).map_err(|_| String::from("cannot convert matrix"))?;
+            (Box::new(Hill::with_alphabet(&mat, alphabet)?), in_alphabet)
+        }
+        s => return Err(format!("unknown cipher {}", s)),
+    })
+}
+
+fn parse_or<T: std::str::FromStr>(v: &Option<String>, default: T) -> Result<T, String>
+where
+    T::Err: ToString,
+{
+    match v {
+        Some(v) => v.trim().parse().map_err(|e: T::Err| e.to_string()),
+        None => Ok(default),
+    }
+}
+
+/// Applies output layout, same as the web application.
+fn format_encryptor(
+    args: &Args,
+    e: Box<dyn Cipher>,
+    accepts: Accepts,
+) -> Result<Box<dyn Encryptor>, String> {
+    if args.cipher.as_deref() == Some("vigenere-8bit") {
+        return Ok(Box::new(e));
+    }
+
+    Ok(match args.layout.as_deref() {
+        None | Some("grouped") if accepts(b' ') => {
+            return Err(String::from(
+                "grouped layout is ambiguous for alphabets with space, use raw or preserve",
+            ))
+        }
+        None | Some("grouped") => Box::new(<_ as Encryptor>::filter(e, accepts).format(
+            Layout::Grouped {
+                size: parse_or(&args.group_size, 5)?,
+                per_line: parse_or(&args.groups_per_line, 12)?,
+            },
+        )),
+        Some("raw") => Box::new(<_ as Encryptor>::filter(e, accepts).format(Layout::Raw)),
+        Some("preserve") => Box::new(<_ as Encryptor>::preserve(e, accepts)),
+        Some(s) => return Err(format!("unknown layout {}", s)),
+    })
+}
+
+fn format_decryptor(args: &Args, d: Box<dyn Cipher>, accepts: Accepts) -> Box<dyn Decryptor> {
+    match args.layout.as_deref() {
+        Some("preserve") => Box::new(<_ as Decryptor>::preserve(d, accepts)),
+        _ => Box::new(<_ as Decryptor>::filter(d, accepts)),
+    }
+}
+
+/// Transport encoding of ciphertext, if any.
+fn transport(args: &Args) -> Result<Option<Box<dyn Cipher>>, String> {
+    Ok(Some(match args.encoding.as_deref() {
+        None => return Ok(None),
+        Some("hex") => Box::new(HexEncode::new()),
+        Some("base32") => Box::new(Base32Encode::new()),
+        Some("base64") => Box::new(Base64Encode::new()),
+        Some(s) => return Err(format!("unknown encoding {}", s)),
+    }))
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let operator = args
+        .operator
+        .ok_or_else(|| String::from("command is required"))?;
+    let (cipher, accepts) = build_cipher(&args)?;
+    let transport = transport(&args)?;
+
+    let mut input: Box<dyn Read> = match args.input.as_deref() {
+        None | Some("-") => Box::new(io::stdin().lock()),
+        Some(path) => Box::new(BufReader::new(
+            File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?,
+        )),
+    };
+    let output: Box<dyn Write> = match args.output.as_deref() {
+        None | Some("-") => Box::new(io::stdout().lock()),
+        Some(path) => {
+            Box::new(File::create(path).map_err(|e| format!("cannot create {}: {}", path, e))?)
+        }
+    };
+    let output = BufWriter::new(output);
+
+    let r = match operator {
+        Operator::Encrypt => {
+            let mut e = format_encryptor(&args, cipher, accepts)?;
+            if let Some(t) = transport {
+                e = Box::new(e.chain(t));
+            }
+
+            let mut w = EncryptWriter::new(output, e);
+            io::copy(&mut input, &mut w).and_then(|_| w.finish())
+        }
+        Operator::Decrypt => {
+            let mut d = format_decryptor(&args, cipher, accepts);
+            if let Some(t) = transport {
+                d = Box::new(<_ as Decryptor>::chain(t, d));
+            }
+
+            let mut w = DecryptWriter::new(output, d);
+            io::copy(&mut input, &mut w).and_then(|_| w.finish())
+        }
+    };
+
+    r.map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Parsed::Run(v)) => *v,
+        Ok(Parsed::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,95 +1,126 @@
-use yew::AttrValue;
+use std::slice;
 
-use crate::util::ModuloU8;
+use crate::util::Zn;
 
-use super::{CipherError, Decryptor, Encryptor};
-
-const A_UPPER: u8 = 'A' as _;
-const Z_UPPER: u8 = 'Z' as _;
-const A_LOWER: u8 = 'a' as _;
-const Z_LOWER: u8 = 'z' as _;
-
-const MODULO: u8 = Z_UPPER - A_UPPER + 1;
+use super::{describe, Alphabet, CipherError, Decryptor, Encryptor, Trace, TraceStep};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Affine {
-    m: ModuloU8<MODULO>,
-    n: ModuloU8<MODULO>,
-    m_inv: ModuloU8<MODULO>,
+    alphabet: Alphabet,
+    m: Zn,
+    n: Zn,
+    m_inv: Zn,
 
     count: usize,
-    temp: [u8; 2],
+    temp: u8,
+    trace: Trace,
 }
 
 impl Affine {
-    pub fn new(m: u8, n: u8) -> Result<Self, AttrValue> {
+    pub fn new(m: u8, n: u8) -> Result<Self, String> {
+        Self::with_alphabet(m as _, n as _, Alphabet::latin())
+    }
+
+    /// `m` must be coprime to the alphabet size.
+    pub fn with_alphabet(m: u32, n: u32, alphabet: Alphabet) -> Result<Self, String> {
         if m == 0 {
-            return Err(<_>::from("m is 0"));
+            return Err("m is 0".into());
         }
-        let m = <ModuloU8<MODULO>>::from(m);
+        let modulus = alphabet.len() as u32;
+        let m = Zn::new(m, modulus);
         let m_inv = match m.inverse() {
             Ok(v) => v,
-            Err(e) => return Err(<_>::from(format!("{}", e))),
+            Err(e) => return Err(e.to_string()),
         };
-        let n = <_>::from(n);
+        let n = Zn::new(n, modulus);
 
         Ok(Self {
+            alphabet,
             m,
             n,
             m_inv,
 
             count: 0,
-            temp: [0; 2],
+            temp: 0,
+            trace: Trace::default(),
         })
     }
 }
 
 impl Encryptor for Affine {
-    fn encrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let v = match self.alphabet.index(byte) {
+            Some(v) => Zn::new(v as _, self.m.modulus()),
+            None => return Err(CipherError::invalid_byte("Affine", byte, self.count)),
         };
-        byte = (self.m * byte.into() + self.n).into();
+        let p = v;
+        let v = u32::from(self.m * v + self.n);
         self.count += 1;
 
-        self.temp[1] = byte + A_UPPER;
-
-        let nl: u8 = if (self.count / 5) % 12 == 0 {
-            '\n' as _
-        } else {
-            ' ' as _
-        };
-        match self.count % 5 {
-            1 if self.count != 1 => {
-                self.temp[0] = nl;
-                Ok(&self.temp)
-            }
-            _ => Ok(&self.temp[1..]),
-        }
+        self.temp = self.alphabet.symbol(v as _);
+        let (m, n, modulus) = (u32::from(self.m), u32::from(self.n), self.m.modulus());
+        self.trace.record(&[byte], &[self.temp], || {
+            let p = u32::from(p);
+            format!(
+                "{}·{} + {} = {} ≡ {} mod {}",
+                m,
+                describe(byte, p),
+                n,
+                m as u64 * p as u64 + n as u64,
+                describe(self.temp, v),
+                modulus
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
 
 impl Decryptor for Affine {
-    fn decrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let v = match self.alphabet.index(byte) {
+            Some(v) => Zn::new(v as _, self.m.modulus()),
+            None => return Err(CipherError::invalid_byte("Affine", byte, self.count)),
         };
-        byte = ((self.n - byte.into()) * self.m_inv).into();
+        let c = v;
+        let v = u32::from((v - self.n) * self.m_inv);
         self.count += 1;
 
-        self.temp[0] = byte + A_UPPER;
-        Ok(&self.temp[..1])
+        self.temp = self.alphabet.symbol(v as _);
+        let (m_inv, n, modulus) = (u32::from(self.m_inv), u32::from(self.n), self.m.modulus());
+        self.trace.record(&[byte], &[self.temp], || {
+            format!(
+                "{}·({} - {}) ≡ {} mod {}",
+                m_inv,
+                describe(byte, u32::from(c)),
+                n,
+                describe(self.temp, v),
+                modulus
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
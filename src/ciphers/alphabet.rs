@@ -0,0 +1,154 @@
+/// Ordered symbol set of a cipher, symbol index is the value used in arithmetic.
+///
+/// Letters whose other case is not in the set are read as that case,
+/// so `a` is read as `A` in the Latin alphabet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Alphabet {
+    symbols: Vec<u8>,
+    index: [Option<u8>; 256],
+}
+
+impl Alphabet {
+    /// Built-in alphabets with their names.
+    pub const PRESETS: [(&'static str, &'static [u8]); 4] = [
+        ("A-Z (26)", b"ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+        ("A-Z, 0-9 (36)", b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789"),
+        ("A-Z, 0-9, space (37)", b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 "),
+        (
+            "Printable ASCII (95)",
+            b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~",
+        ),
+    ];
+
+    pub fn new(symbols: &[u8]) -> Result<Self, String> {
+        if symbols.len() < 2 {
+            return Err("alphabet needs at least 2 symbols".into());
+        }
+
+        let mut index = [None; 256];
+        for (i, &b) in symbols.iter().enumerate() {
+            let p = &mut index[b as usize];
+            if p.is_some() {
+                return Err(format!("symbol {:?} is repeated in alphabet", b as char));
+            }
+            *p = Some(i as u8);
+        }
+
+        for b in symbols {
+            let other = if b.is_ascii_uppercase() {
+                b.to_ascii_lowercase()
+            } else {
+                b.to_ascii_uppercase()
+            };
+            if index[other as usize].is_none() {
+                index[other as usize] = index[*b as usize];
+            }
+        }
+
+        Ok(Self {
+            symbols: symbols.to_vec(),
+            index,
+        })
+    }
+
+    /// Uppercase Latin letters.
+    pub fn latin() -> Self {
+        Self::new(Self::PRESETS[0].1).unwrap()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Always false, an alphabet has at least 2 symbols.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    #[inline]
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    #[inline]
+    pub fn index(&self, byte: u8) -> Option<u8> {
+        self.index[byte as usize]
+    }
+
+    #[inline]
+    pub fn contains(&self, byte: u8) -> bool {
+        self.index(byte).is_some()
+    }
+
+    /// Symbol of an index below [`Self::len`].
+    #[inline]
+    pub fn symbol(&self, index: u8) -> u8 {
+        self.symbols[index as usize]
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self::latin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets() {
+        let lens: Vec<_> = Alphabet::PRESETS
+            .iter()
+            .map(|(_, s)| Alphabet::new(s).unwrap().len())
+            .collect();
+        assert_eq!(lens, [26, 36, 37, 95]);
+    }
+
+    #[test]
+    fn too_short() {
+        assert!(Alphabet::new(b"").is_err());
+        assert!(Alphabet::new(b"A").is_err());
+        assert_eq!(Alphabet::new(b"AB").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn repeated_symbol() {
+        assert_eq!(
+            Alphabet::new(b"ABCA"),
+            Err(String::from("symbol 'A' is repeated in alphabet"))
+        );
+        // Other case is a different symbol
+        assert!(Alphabet::new(b"Aa").is_ok());
+    }
+
+    #[test]
+    fn every_byte() {
+        let symbols: Vec<u8> = (0..=255).collect();
+        let a = Alphabet::new(&symbols).unwrap();
+        assert_eq!(a.len(), 256);
+        assert_eq!(a.index(255), Some(255));
+        assert_eq!(a.symbol(128), 128);
+    }
+
+    #[test]
+    fn letter_case() {
+        let latin = Alphabet::latin();
+        assert_eq!(latin.index(b'a'), Some(0));
+        assert_eq!(latin.index(b'Z'), Some(25));
+        assert!(!latin.contains(b'0'));
+
+        let custom = Alphabet::new(b"Ab1").unwrap();
+        assert_eq!(custom.index(b'a'), Some(0));
+        assert_eq!(custom.index(b'B'), Some(1));
+        assert_eq!(custom.index(b'1'), Some(2));
+
+        // Both cases are in the set, neither is folded
+        let printable = Alphabet::new(Alphabet::PRESETS[3].1).unwrap();
+        assert_ne!(printable.index(b'a'), printable.index(b'A'));
+        assert_eq!(printable.symbol(printable.index(b'a').unwrap()), b'a');
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+
+use super::{CipherError, Decryptor, Encryptor, TraceStep};
+
+/// Layout of encrypted output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Output is not modified.
+    Raw,
+    /// Output is split into groups of `size` bytes separated by space,
+    /// with `per_line` groups on each line.
+    ///
+    /// Zero `per_line` means everything is on a single line.
+    Grouped { size: usize, per_line: usize },
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::Grouped {
+            size: 5,
+            per_line: 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Format<C> {
+    cipher: C,
+    layout: Layout,
+
+    count: usize,
+    temp: Vec<u8>,
+}
+
+impl<C> Format<C> {
+    pub(super) fn new(cipher: C, layout: Layout) -> Self {
+        Self {
+            cipher,
+            layout,
+
+            count: 0,
+            temp: Vec::new(),
+        }
+    }
+}
+
+fn push_formatted(layout: Layout, count: &mut usize, temp: &mut Vec<u8>, data: &[u8]) {
+    let (size, per_line) = match layout {
+        Layout::Grouped { size, per_line } if size > 0 => (size, per_line),
+        _ => {
+            temp.extend_from_slice(data);
+            return;
+        }
+    };
+
+    for &b in data {
+        if (*count != 0) && count.is_multiple_of(size) {
+            let group = *count / size;
+            if (per_line != 0) && group.is_multiple_of(per_line) {
+                temp.push(b'\n');
+            } else {
+                temp.push(b' ');
+            }
+        }
+        *count += 1;
+        temp.push(b);
+    }
+}
+
+impl<C: Encryptor> Encryptor for Format<C> {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let Self {
+            cipher,
+            layout,
+            count,
+            temp,
+        } = self;
+
+        temp.clear();
+        push_formatted(*layout, count, temp, cipher.encrypt_byte(byte)?);
+        Ok(temp)
+    }
+
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        let mut ret = Vec::new();
+        let v = self.cipher.encrypt_finish()?;
+        push_formatted(self.layout, &mut self.count, &mut ret, &v);
+        Ok(ret)
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Slot {
+    /// Byte that is passed to the cipher.
+    Symbol { lower: bool },
+    /// Byte that is copied to output.
+    Literal(u8),
+}
+
+/// Keeps the layout of the input.
+///
+/// Only bytes accepted by `f` are passed into the cipher.
+/// Other bytes (spaces, digits, punctuation, newlines) are copied
+/// into the output in the same place, and lowercase letters are restored.
+/// Because the cipher never sees passed bytes, key position is not advanced.
+///
+/// Copied bytes are held until the cipher outputs a symbol after them,
+/// as it may still output for symbols before them (eg. a delayed pair or padding).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Preserve<C, F> {
+    cipher: C,
+    f: F,
+
+    offset: usize,
+    pending: VecDeque<Slot>,
+    lower: bool,
+    temp: Vec<u8>,
+}
+
+impl<C, F> Preserve<C, F> {
+    pub(super) fn new(cipher: C, f: F) -> Self {
+        Self {
+            cipher,
+            f,
+
+            offset: 0,
+            pending: VecDeque::new(),
+            lower: false,
+            temp: Vec::new(),
+        }
+    }
+}
+
+fn push_literals(pending: &mut VecDeque<Slot>, temp: &mut Vec<u8>) {
+    while let Some(&Slot::Literal(b)) = pending.front() {
+        temp.push(b);
+        pending.pop_front();
+    }
+}
+
+/// Places output bytes into pending slots.
+///
+/// Literals are only written before the symbol that follows them,
+/// trailing literals stay pending.
+/// Extra output (eg. padding) is put before trailing literals
+/// and follows the case of the previous symbol.
+fn push_preserved(pending: &mut VecDeque<Slot>, lower: &mut bool, temp: &mut Vec<u8>, data: &[u8]) {
+    for &b in data {
+        if pending.iter().any(|s| matches!(s, Slot::Symbol { .. })) {
+            push_literals(pending, temp);
+            if let Some(Slot::Symbol { lower: l }) = pending.pop_front() {
+                *lower = l;
+            }
+        }
+
+        temp.push(if *lower { b.to_ascii_lowercase() } else { b });
+    }
+}
+
+impl<C, F: FnMut(u8) -> bool> Preserve<C, F> {
+    fn process_byte(
+        &mut self,
+        byte: u8,
+        g: impl for<'a> FnOnce(&'a mut C, u8) -> Result<&'a [u8], CipherError>,
+    ) -> Result<&[u8], CipherError> {
+        let Self {
+            cipher,
+            f,
+            offset,
+            pending,
+            lower,
+            temp,
+        } = self;
+        let o = *offset;
+        *offset += 1;
+
+        temp.clear();
+        if f(byte) {
+            pending.push_back(Slot::Symbol {
+                lower: byte.is_ascii_lowercase(),
+            });
+            let v = g(cipher, byte).map_err(|e| e.relocate(o, Some(byte)))?;
+            push_preserved(pending, lower, temp, v);
+        } else {
+            pending.push_back(Slot::Literal(byte));
+        }
+        Ok(temp)
+    }
+
+    fn process_finish(
+        &mut self,
+        g: impl FnOnce(&mut C) -> Result<Vec<u8>, CipherError>,
+    ) -> Result<Vec<u8>, CipherError> {
+        let mut ret = Vec::new();
+        let offset = self.offset;
+        let v = g(&mut self.cipher).map_err(|e| e.relocate(offset, None))?;
+        push_preserved(&mut self.pending, &mut self.lower, &mut ret, &v);
+
+        // Write trailing literals, drop symbols that never got output
+        for s in self.pending.drain(..) {
+            if let Slot::Literal(b) = s {
+                ret.push(b);
+            }
+        }
+        Ok(ret)
+    }
+}
+
+impl<C: Encryptor, F: FnMut(u8) -> bool> Encryptor for Preserve<C, F> {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process_byte(byte, C::encrypt_byte)
+    }
+
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        self.process_finish(C::encrypt_finish)
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
+    }
+}
+
+impl<C: Decryptor, F: FnMut(u8) -> bool> Decryptor for Preserve<C, F> {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process_byte(byte, C::decrypt_byte)
+    }
+
+    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        self.process_finish(C::decrypt_finish)
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Hill, Playfair};
+    use super::*;
+
+    fn encrypt(mut c: impl Encryptor, text: &[u8]) -> String {
+        let mut ret = Vec::new();
+        for &b in text {
+            ret.extend_from_slice(c.encrypt_byte(b).unwrap());
+        }
+        ret.extend(c.encrypt_finish().unwrap());
+        String::from_utf8(ret).unwrap()
+    }
+
+    fn preserve<C: Encryptor>(c: C) -> impl Encryptor {
+        <_ as Encryptor>::preserve(c, |b: u8| b.is_ascii_alphabetic())
+    }
+
+    #[test]
+    fn playfair_late_output_before_trailing_literals() {
+        let key = b"playfair example";
+        assert_eq!(encrypt(Playfair::new(key).unwrap(), b"TREE"), "UIXMXM");
+
+        // Last pair of doubled letters is only output on finish
+        assert_eq!(
+            encrypt(preserve(Playfair::new(key).unwrap()), b"Tree!"),
+            "Uixmxm!"
+        );
+        assert_eq!(
+            encrypt(preserve(Playfair::new(key).unwrap()), b"tree, tree!\n"),
+            "uixm, ivexxm!\n"
+        );
+    }
+
+    #[test]
+    fn hill_padding_before_trailing_literals() {
+        let key = [5, 2, 3, 7];
+        assert_eq!(encrypt(Hill::new(&key).unwrap(), b"HEY"), "RXQU");
+
+        assert_eq!(
+            encrypt(preserve(Hill::new(&key).unwrap()), b"Hey!"),
+            "Rxqu!"
+        );
+        assert_eq!(
+            encrypt(preserve(Hill::new(&key).unwrap()), b"Hey, yo!"),
+            "Rxm, gsq!"
+        );
+    }
+}
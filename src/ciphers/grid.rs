@@ -0,0 +1,87 @@
+use super::Alphabet;
+
+/// Keyed rectangular grid of symbols (Polybius square).
+///
+/// Cells are filled row by row with the key symbols first, then the rest of the alphabet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid {
+    rows: usize,
+    cols: usize,
+    /// Symbols in row-major order.
+    symbols: Vec<u8>,
+    /// Cell of every alphabet index.
+    cells: Vec<u8>,
+    alphabet: Alphabet,
+}
+
+impl Grid {
+    /// Key symbols outside the alphabet are skipped.
+    pub fn new(key: &[u8], alphabet: Alphabet, rows: usize) -> Result<Self, String> {
+        let len = alphabet.len();
+        if (rows == 0) || !len.is_multiple_of(rows) {
+            return Err(format!(
+                "{} symbols cannot be split into {} rows",
+                len, rows
+            ));
+        }
+
+        let mut cells = vec![None; len];
+        let mut symbols = Vec::with_capacity(len);
+        for i in key
+            .iter()
+            .filter_map(|&b| alphabet.index(b))
+            .chain(0..=(len - 1) as u8)
+        {
+            let c = &mut cells[i as usize];
+            if c.is_none() {
+                *c = Some(symbols.len() as u8);
+                symbols.push(alphabet.symbol(i));
+            }
+        }
+
+        Ok(Self {
+            rows,
+            cols: len / rows,
+            symbols,
+            cells: cells.into_iter().flatten().collect(),
+            alphabet,
+        })
+    }
+
+    /// 6×6 grid of letters and digits.
+    pub fn alphanumeric(key: &[u8]) -> Self {
+        Self::new(key, Alphabet::new(Alphabet::PRESETS[1].1).unwrap(), 6).unwrap()
+    }
+
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Symbols in row-major order.
+    #[inline]
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    #[inline]
+    pub fn contains(&self, byte: u8) -> bool {
+        self.alphabet.contains(byte)
+    }
+
+    /// Row and column of a symbol.
+    pub fn position(&self, byte: u8) -> Option<(usize, usize)> {
+        let i = self.cells[self.alphabet.index(byte)? as usize] as usize;
+        Some((i / self.cols, i % self.cols))
+    }
+
+    #[inline]
+    pub fn symbol(&self, row: usize, col: usize) -> u8 {
+        self.symbols[row * self.cols + col]
+    }
+}
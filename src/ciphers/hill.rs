@@ -1,29 +1,26 @@
-use std::mem;
+use crate::util::{MatrixZn, Zn};
 
-use yew::AttrValue;
-
-use crate::util::{MatrixU8, ModuloU8};
-
-use super::{empty_slice, CipherError, Decryptor, Encryptor};
-
-const A_UPPER: u8 = 'A' as _;
-const Z_UPPER: u8 = 'Z' as _;
-const A_LOWER: u8 = 'a' as _;
-const Z_LOWER: u8 = 'z' as _;
-
-const MODULO: u8 = Z_UPPER - A_UPPER + 1;
+use super::{empty_slice, Alphabet, CipherError, Decryptor, Encryptor, Trace, TraceStep};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Hill {
-    mat: MatrixU8<MODULO>,
-    mat_inv: MatrixU8<MODULO>,
+    alphabet: Alphabet,
+    mat: MatrixZn,
+    mat_inv: MatrixZn,
 
     count: usize,
-    temp: Vec<ModuloU8<MODULO>>,
+    temp: Vec<Zn>,
+    out: Vec<u8>,
+    trace: Trace,
 }
 
 impl Hill {
-    pub fn new(mat: &[u8]) -> Result<Self, AttrValue> {
+    pub fn new(mat: &[u8]) -> Result<Self, String> {
+        Self::with_alphabet(mat, Alphabet::latin())
+    }
+
+    /// Matrix is taken modulo the alphabet size.
+    pub fn with_alphabet(mat: &[u8], alphabet: Alphabet) -> Result<Self, String> {
         let mut n = 1;
         loop {
             let (a, b) = (n * n, mat.len());
@@ -36,33 +33,29 @@ impl Hill {
             }
         }
 
-        let mat = <MatrixU8<MODULO>>::new(
-            n,
-            mat.into_iter()
-                .map(|v| <ModuloU8<MODULO>>::from(*v))
-                .collect(),
-        );
+        let modulus = alphabet.len() as u32;
+        let mat = MatrixZn::new(n, modulus, mat.iter().map(|&v| v as u32));
         let mat_inv = match mat.inverse() {
             Ok(v) => v,
-            Err(e) => return Err(format!("{}", e).into()),
+            Err(e) => return Err(e.to_string()),
         };
 
         Ok(Self {
+            alphabet,
             mat,
             mat_inv,
 
             count: 0,
-            temp: vec![0.into(); n * 2],
+            temp: vec![Zn::new(0, modulus); n * 2],
+            out: vec![0; n],
+            trace: Trace::default(),
         })
     }
-}
 
-impl Encryptor for Hill {
-    fn encrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
+    fn process(&mut self, byte: u8, encrypt: bool) -> Result<&[u8], CipherError> {
+        let byte = match self.alphabet.index(byte) {
+            Some(v) => v,
+            None => return Err(CipherError::invalid_byte("Hill", byte, self.count)),
         };
 
         let size = self.mat.size();
@@ -71,74 +64,105 @@ impl Encryptor for Hill {
 
         {
             let i = count % size;
-            self.temp[i] = byte.into();
+            self.temp[i] = Zn::new(byte as _, self.mat.modulus());
             if i != size - 1 {
                 return Ok(empty_slice());
             }
         }
 
         let (a, b) = self.temp.split_at_mut(size);
-        self.mat.slice_mult(a, &mut *b);
+        if encrypt {
+            self.mat.slice_mult(a, &mut *b);
+        } else {
+            self.mat_inv.slice_mult(a, &mut *b);
+        }
 
-        // SAFETY: ModuloU8 is transparently represented by u8
-        unsafe {
-            let ret: &mut [u8] = mem::transmute(b);
-            for i in ret.iter_mut() {
-                *i += A_UPPER;
-            }
-            Ok(ret)
+        for (o, &v) in self.out.iter_mut().zip(b.iter()) {
+            *o = self.alphabet.symbol(u32::from(v) as _);
+        }
+
+        if self.trace.is_enabled() {
+            let input: Vec<_> = a
+                .iter()
+                .map(|&v| self.alphabet.symbol(u32::from(v) as _))
+                .collect();
+            let mat = if encrypt { &self.mat } else { &self.mat_inv };
+            let detail = product_detail(mat, a, b);
+            self.trace.record(&input, &self.out, || detail);
         }
+        Ok(&self.out)
+    }
+}
+
+fn list(v: impl IntoIterator<Item = impl ToString>) -> String {
+    let v: Vec<_> = v.into_iter().map(|v| v.to_string()).collect();
+    format!("[{}]", v.join(", "))
+}
+
+/// Matrix product of a block, eg. `[[3, 3], [2, 5]]·[7, 4] = [33, 34] ≡ [7, 8] mod 26`.
+fn product_detail(mat: &MatrixZn, block: &[Zn], out: &[Zn]) -> String {
+    let rows = mat.as_slice().chunks(mat.size());
+    let raw = rows.clone().map(|r| {
+        r.iter()
+            .zip(block)
+            .map(|(&m, &v)| u32::from(m) as u64 * u32::from(v) as u64)
+            .sum::<u64>()
+    });
+
+    format!(
+        "{}·{} = {} ≡ {} mod {}",
+        list(rows.map(|r| list(r.iter().map(|&v| u32::from(v))))),
+        list(block.iter().map(|&v| u32::from(v))),
+        list(raw),
+        list(out.iter().map(|&v| u32::from(v))),
+        mat.modulus()
+    )
+}
+
+impl Encryptor for Hill {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte, true)
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         let mut ret = Vec::new();
         let size = self.mat.size();
+        let pad = self.alphabet.symbol(0);
         while self.count % size != 0 {
-            ret.extend_from_slice(self.encrypt_byte('A' as _)?);
+            ret.extend_from_slice(self.encrypt_byte(pad)?);
         }
 
         Ok(ret)
     }
-}
 
-impl Decryptor for Hill {
-    fn decrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
-        };
-
-        let size = self.mat.size();
-        let count = self.count;
-        self.count += 1;
-
-        {
-            let i = count % size;
-            self.temp[i] = byte.into();
-            if i != size - 1 {
-                return Ok(empty_slice());
-            }
-        }
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
 
-        let (a, b) = self.temp.split_at_mut(size);
-        self.mat_inv.slice_mult(a, &mut *b);
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
 
-        // SAFETY: ModuloU8 is transparently represented by u8
-        unsafe {
-            let ret: &mut [u8] = mem::transmute(b);
-            for i in ret.iter_mut() {
-                *i += A_UPPER;
-            }
-            Ok(ret)
-        }
+impl Decryptor for Hill {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte, false)
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        if self.count % self.mat.size() == 0 {
+        let size = self.mat.size();
+        if self.count % size == 0 {
             Ok(Vec::new())
         } else {
-            Err(CipherError::new())
+            Err(CipherError::incomplete_block("Hill", size, self.count))
         }
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
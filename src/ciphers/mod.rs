@@ -1,41 +1,200 @@
 pub mod affine;
+pub mod alphabet;
+pub mod format;
+pub mod grid;
 pub mod hill;
 pub mod playfair;
+pub mod square;
+pub mod stream;
+pub mod transport;
 pub mod vigenere;
 
-use std::marker::PhantomData;
+use std::error::Error;
 use std::mem;
 use std::{fmt, ptr, slice};
 
 pub use affine::*;
+pub use alphabet::*;
+pub use format::*;
+pub use grid::*;
 pub use hill::*;
 pub use playfair::*;
+pub use square::*;
+pub use stream::*;
+pub use transport::*;
 pub use vigenere::*;
 
-pub struct CipherError(PhantomData<()>);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CipherErrorKind {
+    /// Byte is not accepted by the cipher.
+    InvalidByte(u8),
+    /// Key does not contain any usable symbol.
+    EmptyKey,
+    /// Stream ended in the middle of a block of the given size.
+    IncompleteBlock(usize),
+    /// Key stream ended before the input.
+    KeyExhausted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CipherError {
+    cipher: &'static str,
+    kind: CipherErrorKind,
+    offset: usize,
+}
 
 impl CipherError {
-    pub fn new() -> Self {
-        Self(PhantomData)
+    pub fn new(cipher: &'static str, kind: CipherErrorKind, offset: usize) -> Self {
+        Self {
+            cipher,
+            kind,
+            offset,
+        }
+    }
+
+    pub fn invalid_byte(cipher: &'static str, byte: u8, offset: usize) -> Self {
+        Self::new(cipher, CipherErrorKind::InvalidByte(byte), offset)
+    }
+
+    pub fn empty_key(cipher: &'static str, offset: usize) -> Self {
+        Self::new(cipher, CipherErrorKind::EmptyKey, offset)
+    }
+
+    pub fn incomplete_block(cipher: &'static str, size: usize, offset: usize) -> Self {
+        Self::new(cipher, CipherErrorKind::IncompleteBlock(size), offset)
+    }
+
+    pub fn key_exhausted(cipher: &'static str, offset: usize) -> Self {
+        Self::new(cipher, CipherErrorKind::KeyExhausted, offset)
+    }
+
+    #[inline]
+    pub fn cipher(&self) -> &'static str {
+        self.cipher
+    }
+
+    #[inline]
+    pub fn kind(&self) -> CipherErrorKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Offending byte, if any.
+    pub fn byte(&self) -> Option<u8> {
+        match self.kind {
+            CipherErrorKind::InvalidByte(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Moves error position into the outer stream.
+    ///
+    /// If `byte` is given, it replaces the offending byte
+    /// (eg. the byte before being mapped).
+    fn relocate(mut self, offset: usize, byte: Option<u8>) -> Self {
+        self.offset = offset;
+        if let (CipherErrorKind::InvalidByte(b), Some(v)) = (&mut self.kind, byte) {
+            *b = v;
+        }
+        self
     }
 }
 
 impl fmt::Display for CipherError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Cipher error!")
+        write!(f, "{}: ", self.cipher)?;
+        match self.kind {
+            CipherErrorKind::InvalidByte(b) if b.is_ascii_graphic() => write!(
+                f,
+                "invalid byte {:#04x} ({:?}) at offset {}",
+                b, b as char, self.offset
+            ),
+            CipherErrorKind::InvalidByte(b) => {
+                write!(f, "invalid byte {:#04x} at offset {}", b, self.offset)
+            }
+            CipherErrorKind::EmptyKey => write!(f, "key is empty"),
+            CipherErrorKind::IncompleteBlock(n) => write!(
+                f,
+                "input ended at offset {} in the middle of a {}-byte block",
+                self.offset, n
+            ),
+            CipherErrorKind::KeyExhausted => {
+                write!(f, "key ran out at offset {}", self.offset)
+            }
+        }
     }
 }
 
-impl fmt::Debug for CipherError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Display>::fmt(&self, f)
+impl Error for CipherError {}
+
+/// One step of a cipher, recorded while tracing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TraceStep {
+    /// Symbols processed by the step.
+    pub input: Vec<u8>,
+    /// Symbols output by the step.
+    pub output: Vec<u8>,
+    /// Operation applied, eg. `H (7) + K (10) = R (17) mod 26`.
+    pub detail: String,
+}
+
+/// Recorder of [`TraceStep`]s, disabled by default.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Trace(Option<Vec<TraceStep>>);
+
+impl Trace {
+    /// Disabling drops the recorded steps.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.0 = None;
+        } else if self.0.is_none() {
+            self.0 = Some(Vec::new());
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
     }
+
+    /// Adds a step, `detail` is only called while enabled.
+    pub fn record(&mut self, input: &[u8], output: &[u8], detail: impl FnOnce() -> String) {
+        if let Some(v) = &mut self.0 {
+            v.push(TraceStep {
+                input: input.to_vec(),
+                output: output.to_vec(),
+                detail: detail(),
+            });
+        }
+    }
+
+    /// Recorded steps since the last call.
+    pub fn take(&mut self) -> Vec<TraceStep> {
+        self.0.as_mut().map(mem::take).unwrap_or_default()
+    }
+}
+
+/// Symbol with its value, eg. `H (7)`.
+fn describe(symbol: u8, value: impl fmt::Display) -> String {
+    format!("{} ({})", symbol.escape_ascii(), value)
 }
 
 pub trait Encryptor {
     fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError>;
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError>;
 
+    /// Starts or stops recording [`TraceStep`]s, ignored by ciphers without tracing.
+    fn set_tracing(&mut self, _enabled: bool) {}
+
+    /// Steps recorded since the last call.
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        Vec::new()
+    }
+
     fn chain<C>(self, other: C) -> Chain<Self, C>
     where
         Self: Sized,
@@ -44,6 +203,7 @@ pub trait Encryptor {
         Chain {
             child_1: self,
             child_2: other,
+            offset: 0,
             temp: Vec::new(),
         }
     }
@@ -60,7 +220,11 @@ pub trait Encryptor {
         Self: Sized,
         F: Fn(u8) -> u8,
     {
-        Map { cipher: self, f }
+        Map {
+            cipher: self,
+            f,
+            offset: 0,
+        }
     }
 
     fn filter<F>(self, f: F) -> Filter<Self, F>
@@ -68,7 +232,11 @@ pub trait Encryptor {
         Self: Sized,
         F: Fn(u8) -> bool,
     {
-        Filter { cipher: self, f }
+        Filter {
+            cipher: self,
+            f,
+            offset: 0,
+        }
     }
 
     fn filter_map<F>(self, f: F) -> FilterMap<Self, F>
@@ -76,7 +244,26 @@ pub trait Encryptor {
         Self: Sized,
         F: Fn(u8) -> Option<u8>,
     {
-        FilterMap { cipher: self, f }
+        FilterMap {
+            cipher: self,
+            f,
+            offset: 0,
+        }
+    }
+
+    fn format(self, layout: Layout) -> Format<Self>
+    where
+        Self: Sized,
+    {
+        Format::new(self, layout)
+    }
+
+    fn preserve<F>(self, f: F) -> Preserve<Self, F>
+    where
+        Self: Sized,
+        F: Fn(u8) -> bool,
+    {
+        Preserve::new(self, f)
     }
 }
 
@@ -84,6 +271,14 @@ pub trait Decryptor {
     fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError>;
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError>;
 
+    /// Starts or stops recording [`TraceStep`]s, ignored by ciphers without tracing.
+    fn set_tracing(&mut self, _enabled: bool) {}
+
+    /// Steps recorded since the last call.
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        Vec::new()
+    }
+
     fn chain<D>(self, other: D) -> Chain<Self, D>
     where
         Self: Sized,
@@ -92,6 +287,7 @@ pub trait Decryptor {
         Chain {
             child_1: self,
             child_2: other,
+            offset: 0,
             temp: Vec::new(),
         }
     }
@@ -108,7 +304,11 @@ pub trait Decryptor {
         Self: Sized,
         F: Fn(u8) -> u8,
     {
-        Map { cipher: self, f }
+        Map {
+            cipher: self,
+            f,
+            offset: 0,
+        }
     }
 
     fn filter<F>(self, f: F) -> Filter<Self, F>
@@ -116,7 +316,11 @@ pub trait Decryptor {
         Self: Sized,
         F: Fn(u8) -> bool,
     {
-        Filter { cipher: self, f }
+        Filter {
+            cipher: self,
+            f,
+            offset: 0,
+        }
     }
 
     fn filter_map<F>(self, f: F) -> FilterMap<Self, F>
@@ -124,7 +328,55 @@ pub trait Decryptor {
         Self: Sized,
         F: Fn(u8) -> Option<u8>,
     {
-        FilterMap { cipher: self, f }
+        FilterMap {
+            cipher: self,
+            f,
+            offset: 0,
+        }
+    }
+
+    fn preserve<F>(self, f: F) -> Preserve<Self, F>
+    where
+        Self: Sized,
+        F: Fn(u8) -> bool,
+    {
+        Preserve::new(self, f)
+    }
+}
+
+impl<T: Encryptor + ?Sized> Encryptor for Box<T> {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        (**self).encrypt_byte(byte)
+    }
+
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        (**self).encrypt_finish()
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        (**self).set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        (**self).take_trace()
+    }
+}
+
+impl<T: Decryptor + ?Sized> Decryptor for Box<T> {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        (**self).decrypt_byte(byte)
+    }
+
+    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        (**self).decrypt_finish()
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        (**self).set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        (**self).take_trace()
     }
 }
 
@@ -132,18 +384,27 @@ pub trait Decryptor {
 pub struct Chain<C1, C2> {
     child_1: C1,
     child_2: C2,
+
+    offset: usize,
     temp: Vec<u8>,
 }
 
 impl<C1: Encryptor, C2: Encryptor> Encryptor for Chain<C1, C2> {
     fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let offset = self.offset;
+        self.offset += 1;
+
         self.temp.clear();
-        for &byte in self.child_1.encrypt_byte(byte)? {
+        let v = self
+            .child_1
+            .encrypt_byte(byte)
+            .map_err(|e| e.relocate(offset, Some(byte)))?;
+        for &byte in v {
             match self.child_2.encrypt_byte(byte) {
                 Ok(v) => self.temp.extend_from_slice(v),
                 Err(e) => {
                     self.temp.clear();
-                    return Err(e);
+                    return Err(e.relocate(offset, None));
                 }
             }
         }
@@ -151,25 +412,55 @@ impl<C1: Encryptor, C2: Encryptor> Encryptor for Chain<C1, C2> {
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        let offset = self.offset;
         self.temp.clear();
-        for byte in self.child_1.encrypt_finish()? {
-            self.temp
-                .extend_from_slice(self.child_2.encrypt_byte(byte)?);
+        for byte in self
+            .child_1
+            .encrypt_finish()
+            .map_err(|e| e.relocate(offset, None))?
+        {
+            self.temp.extend_from_slice(
+                self.child_2
+                    .encrypt_byte(byte)
+                    .map_err(|e| e.relocate(offset, None))?,
+            );
         }
-        self.temp.extend(self.child_2.encrypt_finish()?);
-        Ok(mem::replace(&mut self.temp, Vec::new()))
+        self.temp.extend(
+            self.child_2
+                .encrypt_finish()
+                .map_err(|e| e.relocate(offset, None))?,
+        );
+        Ok(mem::take(&mut self.temp))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.child_1.set_tracing(enabled);
+        self.child_2.set_tracing(enabled);
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        let mut ret = self.child_1.take_trace();
+        ret.extend(self.child_2.take_trace());
+        ret
     }
 }
 
 impl<C1: Decryptor, C2: Decryptor> Decryptor for Chain<C1, C2> {
     fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let offset = self.offset;
+        self.offset += 1;
+
         self.temp.clear();
-        for &byte in self.child_1.decrypt_byte(byte)? {
+        let v = self
+            .child_1
+            .decrypt_byte(byte)
+            .map_err(|e| e.relocate(offset, Some(byte)))?;
+        for &byte in v {
             match self.child_2.decrypt_byte(byte) {
                 Ok(v) => self.temp.extend_from_slice(v),
                 Err(e) => {
                     self.temp.clear();
-                    return Err(e);
+                    return Err(e.relocate(offset, None));
                 }
             }
         }
@@ -177,13 +468,36 @@ impl<C1: Decryptor, C2: Decryptor> Decryptor for Chain<C1, C2> {
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        let offset = self.offset;
         self.temp.clear();
-        for byte in self.child_1.decrypt_finish()? {
-            self.temp
-                .extend_from_slice(self.child_2.decrypt_byte(byte)?);
+        for byte in self
+            .child_1
+            .decrypt_finish()
+            .map_err(|e| e.relocate(offset, None))?
+        {
+            self.temp.extend_from_slice(
+                self.child_2
+                    .decrypt_byte(byte)
+                    .map_err(|e| e.relocate(offset, None))?,
+            );
         }
-        self.temp.extend(self.child_2.decrypt_finish()?);
-        Ok(mem::replace(&mut self.temp, Vec::new()))
+        self.temp.extend(
+            self.child_2
+                .decrypt_finish()
+                .map_err(|e| e.relocate(offset, None))?,
+        );
+        Ok(mem::take(&mut self.temp))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.child_1.set_tracing(enabled);
+        self.child_2.set_tracing(enabled);
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        let mut ret = self.child_1.take_trace();
+        ret.extend(self.child_2.take_trace());
+        ret
     }
 }
 
@@ -198,6 +512,14 @@ impl<T: Encryptor> Decryptor for Invert<T> {
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         self.0.encrypt_finish()
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.0.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.0.take_trace()
+    }
 }
 
 impl<T: Decryptor> Encryptor for Invert<T> {
@@ -208,33 +530,73 @@ impl<T: Decryptor> Encryptor for Invert<T> {
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         self.0.decrypt_finish()
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.0.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.0.take_trace()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Map<C, F> {
     cipher: C,
     f: F,
+
+    offset: usize,
 }
 
 impl<C: Encryptor, F: FnMut(u8) -> u8> Encryptor for Map<C, F> {
     fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
-        let f = &mut self.f;
-        self.cipher.encrypt_byte(f(byte))
+        let Self { cipher, f, offset } = self;
+        let o = *offset;
+        *offset += 1;
+        cipher
+            .encrypt_byte(f(byte))
+            .map_err(|e| e.relocate(o, Some(byte)))
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        self.cipher.encrypt_finish()
+        let offset = self.offset;
+        self.cipher
+            .encrypt_finish()
+            .map_err(|e| e.relocate(offset, None))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
     }
 }
 
 impl<C: Decryptor, F: FnMut(u8) -> u8> Decryptor for Map<C, F> {
     fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
-        let f = &mut self.f;
-        self.cipher.decrypt_byte(f(byte))
+        let Self { cipher, f, offset } = self;
+        let o = *offset;
+        *offset += 1;
+        cipher
+            .decrypt_byte(f(byte))
+            .map_err(|e| e.relocate(o, Some(byte)))
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        self.cipher.decrypt_finish()
+        let offset = self.offset;
+        self.cipher
+            .decrypt_finish()
+            .map_err(|e| e.relocate(offset, None))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
     }
 }
 
@@ -242,35 +604,67 @@ impl<C: Decryptor, F: FnMut(u8) -> u8> Decryptor for Map<C, F> {
 pub struct Filter<C, F> {
     cipher: C,
     f: F,
+
+    offset: usize,
 }
 
 impl<C: Encryptor, F: FnMut(u8) -> bool> Encryptor for Filter<C, F> {
     fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
-        let Self { cipher, f } = self;
+        let Self { cipher, f, offset } = self;
+        let o = *offset;
+        *offset += 1;
         if f(byte) {
-            cipher.encrypt_byte(byte)
+            cipher
+                .encrypt_byte(byte)
+                .map_err(|e| e.relocate(o, Some(byte)))
         } else {
             Ok(empty_slice())
         }
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        self.cipher.encrypt_finish()
+        let offset = self.offset;
+        self.cipher
+            .encrypt_finish()
+            .map_err(|e| e.relocate(offset, None))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
     }
 }
 
 impl<C: Decryptor, F: FnMut(u8) -> bool> Decryptor for Filter<C, F> {
     fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
-        let Self { cipher, f } = self;
+        let Self { cipher, f, offset } = self;
+        let o = *offset;
+        *offset += 1;
         if f(byte) {
-            cipher.decrypt_byte(byte)
+            cipher
+                .decrypt_byte(byte)
+                .map_err(|e| e.relocate(o, Some(byte)))
         } else {
             Ok(empty_slice())
         }
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        self.cipher.decrypt_finish()
+        let offset = self.offset;
+        self.cipher
+            .decrypt_finish()
+            .map_err(|e| e.relocate(offset, None))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
     }
 }
 
@@ -278,33 +672,65 @@ impl<C: Decryptor, F: FnMut(u8) -> bool> Decryptor for Filter<C, F> {
 pub struct FilterMap<C, F> {
     cipher: C,
     f: F,
+
+    offset: usize,
 }
 
 impl<C: Encryptor, F: FnMut(u8) -> Option<u8>> Encryptor for FilterMap<C, F> {
     fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
-        let Self { cipher, f } = self;
+        let Self { cipher, f, offset } = self;
+        let o = *offset;
+        *offset += 1;
         match f(byte) {
-            Some(b) => cipher.encrypt_byte(b),
+            Some(b) => cipher
+                .encrypt_byte(b)
+                .map_err(|e| e.relocate(o, Some(byte))),
             None => Ok(empty_slice()),
         }
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        self.cipher.encrypt_finish()
+        let offset = self.offset;
+        self.cipher
+            .encrypt_finish()
+            .map_err(|e| e.relocate(offset, None))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
     }
 }
 
 impl<C: Decryptor, F: FnMut(u8) -> Option<u8>> Decryptor for FilterMap<C, F> {
     fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
-        let Self { cipher, f } = self;
+        let Self { cipher, f, offset } = self;
+        let o = *offset;
+        *offset += 1;
         match f(byte) {
-            Some(b) => cipher.decrypt_byte(b),
+            Some(b) => cipher
+                .decrypt_byte(b)
+                .map_err(|e| e.relocate(o, Some(byte))),
             None => Ok(empty_slice()),
         }
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        self.cipher.decrypt_finish()
+        let offset = self.offset;
+        self.cipher
+            .decrypt_finish()
+            .map_err(|e| e.relocate(offset, None))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
     }
 }
 
@@ -1,262 +1,524 @@
 use std::mem;
 
-use super::{empty_slice, CipherError, Decryptor, Encryptor};
+use super::{Alphabet, CipherError, Decryptor, Encryptor, Grid, Trace, TraceStep};
+
+/// Letters sharing a cell, so 25 letters fit the square.
+///
+/// Only applies to letters missing from the grid.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Merge {
+    /// J is read as I.
+    #[default]
+    IJ,
+    /// Q is left out of the square and dropped from plaintext.
+    DropQ,
+    /// K is read as C.
+    CK,
+}
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Playfair {
-    key: [u8; 25],
-    key_inv: [u8; 25],
+impl Merge {
+    /// Letter without its own cell.
+    fn letter(self) -> u8 {
+        match self {
+            Self::IJ => b'J',
+            Self::DropQ => b'Q',
+            Self::CK => b'K',
+        }
+    }
 
-    count: usize,
-    temp: [u8; 4],
-}
+    /// Letter sharing the cell, if any.
+    fn partner(self) -> Option<u8> {
+        match self {
+            Self::IJ => Some(b'I'),
+            Self::DropQ => None,
+            Self::CK => Some(b'C'),
+        }
+    }
 
-fn byte_mapping(v: u8) -> Option<u8> {
-    match v as _ {
-        'A' | 'a' => Some(0),
-        'B' | 'b' => Some(1),
-        'C' | 'c' => Some(2),
-        'D' | 'd' => Some(3),
-        'E' | 'e' => Some(4),
-        'F' | 'f' => Some(5),
-        'G' | 'g' => Some(6),
-        'H' | 'h' => Some(7),
-        'I' | 'i' | 'J' | 'j' => Some(8),
-        'K' | 'k' => Some(9),
-        'L' | 'l' => Some(10),
-        'M' | 'm' => Some(11),
-        'N' | 'n' => Some(12),
-        'O' | 'o' => Some(13),
-        'P' | 'p' => Some(14),
-        'Q' | 'q' => Some(15),
-        'R' | 'r' => Some(16),
-        'S' | 's' => Some(17),
-        'T' | 't' => Some(18),
-        'U' | 'u' => Some(19),
-        'V' | 'v' => Some(20),
-        'W' | 'w' => Some(21),
-        'X' | 'x' => Some(22),
-        'Y' | 'y' => Some(23),
-        'Z' | 'z' => Some(24),
-        _ => None,
+    /// Cell index of a byte, reading the merged letter as its partner.
+    pub(super) fn cell(self, grid: &Grid, byte: u8) -> Option<usize> {
+        let byte = match self.partner() {
+            Some(p) if (byte.to_ascii_uppercase() == self.letter()) && !grid.contains(byte) => p,
+            _ => byte,
+        };
+        let (r, c) = grid.position(byte)?;
+        Some(r * grid.cols() + c)
+    }
+
+    /// Whether the byte is the merged letter without a partner.
+    pub(super) fn is_dropped(self, grid: &Grid, byte: u8) -> bool {
+        self.partner().is_none()
+            && (byte.to_ascii_uppercase() == self.letter())
+            && !grid.contains(byte)
     }
 }
 
-fn byte_unmapping(v: u8) -> u8 {
-    match v {
-        0 => 'A' as _,
-        1 => 'B' as _,
-        2 => 'C' as _,
-        3 => 'D' as _,
-        4 => 'E' as _,
-        5 => 'F' as _,
-        6 => 'G' as _,
-        7 => 'H' as _,
-        8 => 'I' as _,
-        9 => 'K' as _,
-        10 => 'L' as _,
-        11 => 'M' as _,
-        12 => 'N' as _,
-        13 => 'O' as _,
-        14 => 'P' as _,
-        15 => 'Q' as _,
-        16 => 'R' as _,
-        17 => 'S' as _,
-        18 => 'T' as _,
-        19 => 'U' as _,
-        20 => 'V' as _,
-        21 => 'W' as _,
-        22 => 'X' as _,
-        23 => 'Y' as _,
-        24 => 'Z' as _,
-        _ => unreachable!(),
+/// 5×5 square of letters, filled with the key letters then the rest of the alphabet.
+///
+/// The merged letter of the key is replaced with its partner.
+pub fn keysquare(key: &[u8], merge: Merge) -> Grid {
+    let merged = merge.letter();
+    let key: Vec<_> = key
+        .iter()
+        .map(|b| b.to_ascii_uppercase())
+        .map(|b| match merge.partner() {
+            Some(p) if b == merged => p,
+            _ => b,
+        })
+        .collect();
+    let letters: Vec<_> = (b'A'..=b'Z').filter(|&b| b != merged).collect();
+    Grid::new(&key, Alphabet::new(&letters).unwrap(), 5).unwrap()
+}
+
+/// Where fillers are inserted between doubled letters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Doubles {
+    /// Between doubled letters of the same pair.
+    #[default]
+    Pair,
+    /// Between every doubled letters, also across pair boundaries.
+    All,
+    /// Doubled letters are encrypted as is.
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlayfairOptions {
+    /// Inserted between doubled letters and after odd-length plaintext.
+    pub filler: u8,
+    pub merge: Merge,
+    pub doubles: Doubles,
+}
+
+impl Default for PlayfairOptions {
+    fn default() -> Self {
+        Self {
+            filler: b'X',
+            merge: Merge::default(),
+            doubles: Doubles::default(),
+        }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Playfair {
+    options: PlayfairOptions,
+    grid: Grid,
+    /// Cell of filler.
+    filler: usize,
+
+    /// First cell of the current pair.
+    pending: Option<usize>,
+    /// Previous plaintext cell.
+    prev: Option<usize>,
+    offset: usize,
+    temp: Vec<u8>,
+    trace: Trace,
+}
+
 impl Playfair {
     pub fn new(key: &[u8]) -> Result<Self, &'static str> {
-        let mut ix = 0;
-        let mut key_ = [0u8; 25];
-        let mut key_inv = [0u8; 25];
-        let mut mask = 0u32;
-
-        for &k in key {
-            let k = match byte_mapping(k) {
-                Some(v) => v,
-                None => continue,
-            };
+        Self::with_options(key, PlayfairOptions::default())
+    }
 
-            let m = 1u32.wrapping_shl(k as _);
-            if mask & m != 0 {
-                continue;
-            }
-            mask |= m;
+    /// Uses the 5×5 letter square with merged letters of `options`.
+    pub fn with_options(key: &[u8], options: PlayfairOptions) -> Result<Self, &'static str> {
+        Self::with_grid(keysquare(key, options.merge), options)
+    }
 
-            debug_assert!(ix < 25);
-            key_[ix] = k;
-            key_inv[k as usize] = ix as _;
-            ix += 1;
-        }
+    /// Rows and columns of the grid wrap around, any rectangular grid works.
+    pub fn with_grid(grid: Grid, options: PlayfairOptions) -> Result<Self, &'static str> {
+        let mut ret = Self {
+            options,
+            grid,
+            filler: 0,
+
+            pending: None,
+            prev: None,
+            offset: 0,
+            temp: Vec::new(),
+            trace: Trace::default(),
+        };
+        ret.filler = match ret.cell(options.filler) {
+            Some(v) => v,
+            None => return Err("filler must be in the grid"),
+        };
 
-        while ix < 25 {
-            let k = mask.trailing_ones() as u8;
+        Ok(ret)
+    }
 
-            let m = 1u32.wrapping_shl(k as _);
-            debug_assert_eq!(mask & m, 0);
-            mask |= m;
+    #[inline]
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
 
-            key_[ix] = k;
-            key_inv[k as usize] = ix as _;
-            ix += 1;
-        }
+    /// Filler as it appears in the grid.
+    pub fn filler(&self) -> u8 {
+        self.grid.symbols()[self.filler]
+    }
 
-        Ok(Self {
-            key: key_,
-            key_inv,
+    /// Whether the byte is encrypted, or dropped as a merged letter.
+    pub fn accepts(&self, byte: u8) -> bool {
+        self.cell(byte).is_some() || self.is_dropped(byte)
+    }
 
-            count: 0,
-            temp: [0; 4],
-        })
+    fn is_dropped(&self, byte: u8) -> bool {
+        self.options.merge.is_dropped(&self.grid, byte)
     }
 
-    fn encrypt_pair(&self, mut a: u8, mut b: u8) -> (u8, u8) {
-        a = self.key_inv[a as usize];
-        b = self.key_inv[b as usize];
+    fn cell(&self, byte: u8) -> Option<usize> {
+        self.options.merge.cell(&self.grid, byte)
+    }
 
-        let (mut da, mut ra) = (a / 5, a % 5);
-        let (mut db, mut rb) = (b / 5, b % 5);
+    /// Applies the row, column or rectangle rule to a pair of cells.
+    fn pair(&mut self, a: usize, b: usize, encrypt: bool) -> (u8, u8) {
+        let (rows, cols) = (self.grid.rows(), self.grid.cols());
+        let (ra, ca) = (a / cols, a % cols);
+        let (rb, cb) = (b / cols, b % cols);
+        let (dr, dc) = if encrypt {
+            (1, 1)
+        } else {
+            (rows - 1, cols - 1)
+        };
 
-        if da == db {
-            ra = (ra + 1) % 5;
-            rb = (rb + 1) % 5;
-        } else if ra == rb {
-            da = (da + 1) % 5;
-            db = (db + 1) % 5;
+        let (rule, (ra2, ca2), (rb2, cb2)) = if ra == rb {
+            ("same row", (ra, (ca + dc) % cols), (rb, (cb + dc) % cols))
+        } else if ca == cb {
+            (
+                "same column",
+                ((ra + dr) % rows, ca),
+                ((rb + dr) % rows, cb),
+            )
         } else {
-            mem::swap(&mut ra, &mut rb);
-        }
+            ("rectangle", (ra, cb), (rb, ca))
+        };
+        let out = (self.grid.symbol(ra2, ca2), self.grid.symbol(rb2, cb2));
+
+        let input = [self.grid.symbol(ra, ca), self.grid.symbol(rb, cb)];
+        self.trace.record(&input, &[out.0, out.1], || {
+            format!(
+                "{}: ({}, {}) ({}, {}) -> ({}, {}) ({}, {})",
+                rule, ra, ca, rb, cb, ra2, ca2, rb2, cb2
+            )
+        });
+        out
+    }
 
-        a = da * 5 + ra;
-        b = db * 5 + rb;
+    /// Adds plaintext cell, encrypting completed pairs into `temp`.
+    fn push_plain(&mut self, c: usize) {
+        if (self.options.doubles == Doubles::All) && (self.prev == Some(c)) && (c != self.filler) {
+            self.push_pair(self.filler);
+        }
+        self.prev = Some(c);
+        self.push_pair(c);
+    }
 
-        a = self.key[a as usize];
-        b = self.key[b as usize];
+    fn push_pair(&mut self, c: usize) {
+        let Some(a) = self.pending.take() else {
+            self.pending = Some(c);
+            return;
+        };
 
-        (a, b)
+        let (a, b) = if (a == c) && (c != self.filler) && (self.options.doubles != Doubles::Never) {
+            // Doubled letter starts the next pair
+            self.pending = Some(c);
+            self.pair(a, self.filler, true)
+        } else {
+            self.pair(a, c, true)
+        };
+        self.temp.extend_from_slice(&[a, b]);
     }
+}
 
-    fn decrypt_pair(&self, mut a: u8, mut b: u8) -> (u8, u8) {
-        a = self.key_inv[a as usize];
-        b = self.key_inv[b as usize];
+impl Encryptor for Playfair {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let offset = self.offset;
+        self.offset += 1;
+        self.temp.clear();
+
+        match self.cell(byte) {
+            Some(c) => self.push_plain(c),
+            None if self.is_dropped(byte) => (),
+            None => return Err(CipherError::invalid_byte("Playfair", byte, offset)),
+        }
 
-        let (mut da, mut ra) = (a / 5, a % 5);
-        let (mut db, mut rb) = (b / 5, b % 5);
+        Ok(&self.temp)
+    }
 
-        if da == db {
-            ra = (ra + 4) % 5;
-            rb = (rb + 4) % 5;
-        } else if ra == rb {
-            da = (da + 4) % 5;
-            db = (db + 4) % 5;
-        } else {
-            mem::swap(&mut ra, &mut rb);
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        self.temp.clear();
+        if let Some(a) = self.pending.take() {
+            let (a, b) = self.pair(a, self.filler, true);
+            self.temp.extend_from_slice(&[a, b]);
         }
+        self.prev = None;
 
-        a = da * 5 + ra;
-        b = db * 5 + rb;
+        Ok(mem::take(&mut self.temp))
+    }
 
-        a = self.key[a as usize];
-        b = self.key[b as usize];
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
 
-        (a, b)
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
     }
 }
 
-impl Encryptor for Playfair {
-    fn encrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
-        byte = match byte_mapping(byte) {
-            Some(v) => v,
-            None => return Err(CipherError::new()),
+impl Decryptor for Playfair {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let offset = self.offset;
+        self.offset += 1;
+        self.temp.clear();
+
+        let Some(c) = self.cell(byte) else {
+            return Err(CipherError::invalid_byte("Playfair", byte, offset));
         };
+        match self.pending.take() {
+            None => self.pending = Some(c),
+            Some(a) => {
+                let (a, b) = self.pair(a, c, false);
+                self.temp.extend_from_slice(&[a, b]);
+            }
+        }
+
+        Ok(&self.temp)
+    }
+
+    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        if self.pending.take().is_none() {
+            Ok(Vec::new())
+        } else {
+            Err(CipherError::incomplete_block("Playfair", 2, self.offset))
+        }
+    }
 
-        let count = self.count;
-        self.count += 1;
-        if count % 2 == 0 {
-            self.temp[0] = byte;
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
 
-            return Ok(empty_slice());
+/// Removes fillers from decrypted Playfair plaintext.
+///
+/// A filler between two identical letters and a filler at the end are dropped,
+/// which may also drop genuine letters (eg. a final X).
+/// The filler is compared as decrypted, see [`Playfair::filler`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StripFiller<C> {
+    cipher: C,
+    filler: u8,
+
+    pending: Vec<u8>,
+    temp: Vec<u8>,
+}
+
+impl<C> StripFiller<C> {
+    pub fn new(cipher: C, filler: u8) -> Self {
+        Self {
+            cipher,
+            filler,
+
+            pending: Vec::with_capacity(2),
+            temp: Vec::new(),
         }
+    }
+}
 
-        let (mut a, mut b) = (self.temp[0], byte);
-        if (a == b) & (b != 22) {
-            b = 22;
-            self.count += 1;
+fn push_stripped(filler: u8, pending: &mut Vec<u8>, temp: &mut Vec<u8>, b: u8) {
+    match **pending {
+        [] => pending.push(b),
+        [p] if (b == filler) && (p != filler) => pending.push(b),
+        [p] => {
+            temp.push(p);
+            pending[0] = b;
+        }
+        [p, _] if b == p => {
+            temp.push(p);
+            pending.clear();
+            pending.push(b);
         }
+        [p, f] => {
+            temp.push(p);
+            pending.clear();
+            pending.push(f);
+            push_stripped(filler, pending, temp, b);
+        }
+        _ => unreachable!(),
+    }
+}
 
-        (a, b) = self.encrypt_pair(a, b);
+impl<C: Encryptor> Encryptor for StripFiller<C> {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.cipher.encrypt_byte(byte)
+    }
 
-        (self.temp[1], self.temp[2]) = (byte_unmapping(a), byte_unmapping(b));
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        self.cipher.encrypt_finish()
+    }
 
-        let nl: u8 = if (count / 5) % 12 == 0 {
-            '\n' as _
-        } else {
-            ' ' as _
-        };
-        match count % 5 {
-            0 if count != 0 => {
-                self.temp[3] = self.temp[2];
-                self.temp[2] = nl;
-                Ok(&self.temp[1..])
-            }
-            1 if count != 1 => {
-                self.temp[3] = self.temp[2];
-                self.temp[2] = self.temp[1];
-                self.temp[1] = nl;
-                Ok(&self.temp[1..])
-            }
-            _ => Ok(&self.temp[1..3]),
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
+    }
+}
+
+impl<C: Decryptor> Decryptor for StripFiller<C> {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let Self {
+            cipher,
+            filler,
+            pending,
+            temp,
+        } = self;
+
+        temp.clear();
+        for &b in cipher.decrypt_byte(byte)? {
+            push_stripped(*filler, pending, temp, b);
         }
+        Ok(temp)
     }
 
-    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        if self.count % 2 == 0 {
-            Ok(Vec::new())
-        } else {
-            let ret = Ok(Vec::from(self.encrypt_byte('X' as _)?));
-            debug_assert_eq!(self.count % 2, 0);
-            ret
+    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        let Self {
+            cipher,
+            filler,
+            pending,
+            temp,
+        } = self;
+
+        temp.clear();
+        for b in cipher.decrypt_finish()? {
+            push_stripped(*filler, pending, temp, b);
+        }
+        match **pending {
+            [p, _] => temp.push(p),
+            [p] if p != *filler => temp.push(p),
+            _ => (),
         }
+        pending.clear();
+
+        Ok(mem::take(temp))
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.cipher.set_tracing(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.cipher.take_trace()
     }
 }
 
-impl Decryptor for Playfair {
-    fn decrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
-        byte = match byte_mapping(byte) {
-            Some(v) => v,
-            None => return Err(CipherError::new()),
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let count = self.count;
-        self.count += 1;
-        if count % 2 == 0 {
-            self.temp[0] = byte;
-            return Ok(empty_slice());
+    fn encrypt(mut c: impl Encryptor, text: &[u8]) -> String {
+        let mut ret = Vec::new();
+        for &b in text {
+            ret.extend_from_slice(c.encrypt_byte(b).unwrap());
         }
+        ret.extend(c.encrypt_finish().unwrap());
+        String::from_utf8(ret).unwrap()
+    }
 
-        let (mut a, mut b) = (self.temp[0], byte);
+    fn decrypt(mut c: impl Decryptor, text: &[u8]) -> String {
+        let mut ret = Vec::new();
+        for &b in text {
+            ret.extend_from_slice(c.decrypt_byte(b).unwrap());
+        }
+        ret.extend(c.decrypt_finish().unwrap());
+        String::from_utf8(ret).unwrap()
+    }
 
-        (a, b) = self.decrypt_pair(a, b);
+    fn playfair(doubles: Doubles) -> Playfair {
+        let options = PlayfairOptions {
+            doubles,
+            ..Default::default()
+        };
+        Playfair::with_options(b"playfair example", options).unwrap()
+    }
 
-        (self.temp[0], self.temp[1]) = (byte_unmapping(a), byte_unmapping(b));
+    /// Checks ciphertext, decrypted plaintext and plaintext with fillers removed.
+    fn check(doubles: Doubles, plain: &str, cipher: &str, decrypted: &str, stripped: &str) {
+        assert_eq!(encrypt(playfair(doubles), plain.as_bytes()), cipher);
+        assert_eq!(decrypt(playfair(doubles), cipher.as_bytes()), decrypted);
+        let strip = StripFiller::new(playfair(doubles), b'X');
+        assert_eq!(decrypt(strip, cipher.as_bytes()), stripped);
+    }
 
-        Ok(&self.temp[..2])
+    #[test]
+    fn strip_filler() {
+        check(Doubles::Pair, "BALLOON", "DPYRANQO", "BALXLOON", "BALLOON");
+        check(Doubles::Pair, "BOOK", "DKQN", "BOOK", "BOOK");
     }
 
-    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
-        if self.count % 2 == 0 {
-            Ok(Vec::new())
-        } else {
-            Err(CipherError::new())
+    #[test]
+    fn strip_final_filler() {
+        // Padding of odd-length plaintext, the X before it is kept
+        check(Doubles::Pair, "FOX", "ASMM", "FOXX", "FOX");
+        // Genuine final X can not be told apart from padding
+        check(Doubles::Pair, "AX", "YE", "AX", "A");
+    }
+
+    #[test]
+    fn strip_filler_doubles_all() {
+        check(Doubles::All, "BOOK", "DKEQQI", "BOXOKX", "BOOK");
+        check(
+            Doubles::All,
+            "BALLOON",
+            "DPYRANEQQR",
+            "BALXLOXONX",
+            "BALLOON",
+        );
+    }
+
+    #[test]
+    fn strip_filler_doubles_never() {
+        check(Doubles::Never, "BALLOON", "DPAAQQQR", "BALLOONX", "BALLOON");
+    }
+
+    #[test]
+    fn keysquare_merged_letters() {
+        let square = |merge| {
+            let options = PlayfairOptions {
+                merge,
+                ..Default::default()
+            };
+            let c = Playfair::with_options(b"QUICK JUMP", options).unwrap();
+            String::from_utf8(c.grid().symbols().to_vec())
+        };
+        assert_eq!(square(Merge::IJ).unwrap(), "QUICKMPABDEFGHLNORSTVWXYZ");
+        assert_eq!(square(Merge::DropQ).unwrap(), "UICKJMPABDEFGHLNORSTVWXYZ");
+        assert_eq!(square(Merge::CK).unwrap(), "QUICJMPABDEFGHLNORSTVWXYZ");
+    }
+
+    #[test]
+    fn merged_letters() {
+        let cases = [
+            // J is read as I, so I and J form a doubled pair
+            (Merge::IJ, "AICUQKVY", "IXIQKCZX"),
+            // Q is dropped, J has its own cell
+            (Merge::DropQ, "UCJKVY", "JIKCZX"),
+            // K is read as C, J has its own cell
+            (Merge::CK, "QCUJJY", "JIQCCZ"),
+        ];
+        for (merge, cipher, plain) in cases {
+            let options = PlayfairOptions {
+                merge,
+                ..Default::default()
+            };
+            let c = Playfair::with_options(b"QUICK JUMP", options).unwrap();
+            assert_eq!(encrypt(c, b"JIQKCZ"), cipher);
+            let c = Playfair::with_options(b"QUICK JUMP", options).unwrap();
+            assert_eq!(decrypt(c, cipher.as_bytes()), plain);
         }
     }
 }
@@ -0,0 +1,449 @@
+use super::{
+    keysquare, CipherError, Decryptor, Encryptor, Grid, PlayfairOptions, Trace, TraceStep,
+};
+
+/// Two-Square cipher with the squares stacked vertically.
+///
+/// Plaintext pairs are looked up in the upper and lower square, and replaced by
+/// the other corners of their rectangle. Pairs in the same column are left as is.
+/// Encryption and decryption are the same operation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TwoSquare {
+    options: PlayfairOptions,
+    upper: Grid,
+    lower: Grid,
+    /// Cell of filler in the lower square.
+    filler: usize,
+
+    /// Cell of the first byte of the current pair.
+    pending: Option<usize>,
+    offset: usize,
+    temp: Vec<u8>,
+    trace: Trace,
+}
+
+impl TwoSquare {
+    pub fn new(upper: &[u8], lower: &[u8]) -> Self {
+        // Default filler is never merged
+        Self::with_options(upper, lower, PlayfairOptions::default()).unwrap()
+    }
+
+    /// Uses the filler and merged letters of `options`, doubled letters are never split.
+    pub fn with_options(
+        upper: &[u8],
+        lower: &[u8],
+        options: PlayfairOptions,
+    ) -> Result<Self, &'static str> {
+        let upper = keysquare(upper, options.merge);
+        let lower = keysquare(lower, options.merge);
+        let filler = match options.merge.cell(&lower, options.filler) {
+            Some(v) => v,
+            None => return Err("filler must be in the grid"),
+        };
+
+        Ok(Self {
+            options,
+            upper,
+            lower,
+            filler,
+
+            pending: None,
+            offset: 0,
+            temp: Vec::new(),
+            trace: Trace::default(),
+        })
+    }
+
+    #[inline]
+    pub fn upper(&self) -> &Grid {
+        &self.upper
+    }
+
+    #[inline]
+    pub fn lower(&self) -> &Grid {
+        &self.lower
+    }
+
+    /// Whether the byte is encrypted, or dropped as a merged letter.
+    pub fn accepts(&self, byte: u8) -> bool {
+        let merge = self.options.merge;
+        merge.cell(&self.upper, byte).is_some() || merge.is_dropped(&self.upper, byte)
+    }
+
+    fn process(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        let offset = self.offset;
+        self.offset += 1;
+        self.temp.clear();
+
+        let grid = if self.pending.is_none() {
+            &self.upper
+        } else {
+            &self.lower
+        };
+        let merge = self.options.merge;
+        let c = match merge.cell(grid, byte) {
+            Some(c) => c,
+            None if merge.is_dropped(grid, byte) => return Ok(&self.temp),
+            None => return Err(CipherError::invalid_byte("Two-Square", byte, offset)),
+        };
+
+        match self.pending.take() {
+            None => self.pending = Some(c),
+            Some(a) => {
+                let (a, b) = self.pair(a, c);
+                self.temp.extend_from_slice(&[a, b]);
+            }
+        }
+        Ok(&self.temp)
+    }
+
+    fn pair(&mut self, a: usize, b: usize) -> (u8, u8) {
+        let (cols_a, cols_b) = (self.upper.cols(), self.lower.cols());
+        let (ra, ca) = (a / cols_a, a % cols_a);
+        let (rb, cb) = (b / cols_b, b % cols_b);
+        let input = [self.upper.symbol(ra, ca), self.lower.symbol(rb, cb)];
+
+        if ca == cb {
+            self.trace.record(&input, &input, || {
+                format!("same column: ({}, {}) ({}, {})", ra, ca, rb, cb)
+            });
+            (input[0], input[1])
+        } else {
+            let out = (self.upper.symbol(ra, cb), self.lower.symbol(rb, ca));
+            self.trace.record(&input, &[out.0, out.1], || {
+                format!(
+                    "rectangle: ({}, {}) ({}, {}) -> ({}, {}) ({}, {})",
+                    ra, ca, rb, cb, ra, cb, rb, ca
+                )
+            });
+            out
+        }
+    }
+}
+
+impl Encryptor for TwoSquare {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte)
+    }
+
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        let Some(a) = self.pending.take() else {
+            return Ok(Vec::new());
+        };
+        let (a, b) = self.pair(a, self.filler);
+
+        Ok(vec![a, b])
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
+
+impl Decryptor for TwoSquare {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte)
+    }
+
+    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        if self.pending.take().is_none() {
+            Ok(Vec::new())
+        } else {
+            Err(CipherError::incomplete_block("Two-Square", 2, self.offset))
+        }
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
+
+/// Four-Square cipher.
+///
+/// Plaintext pairs are looked up in the upper left and lower right unkeyed squares,
+/// and replaced by the letters in the same rows of the keyed upper right and lower left squares.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FourSquare {
+    options: PlayfairOptions,
+    plain: Grid,
+    upper: Grid,
+    lower: Grid,
+    /// Cell of filler in the unkeyed square.
+    filler: usize,
+
+    /// Cell of the first byte of the current pair.
+    pending: Option<usize>,
+    offset: usize,
+    temp: Vec<u8>,
+    trace: Trace,
+}
+
+impl FourSquare {
+    pub fn new(upper: &[u8], lower: &[u8]) -> Self {
+        // Default filler is never merged
+        Self::with_options(upper, lower, PlayfairOptions::default()).unwrap()
+    }
+
+    /// Uses the filler and merged letters of `options`, doubled letters are never split.
+    pub fn with_options(
+        upper: &[u8],
+        lower: &[u8],
+        options: PlayfairOptions,
+    ) -> Result<Self, &'static str> {
+        let plain = keysquare(&[], options.merge);
+        let filler = match options.merge.cell(&plain, options.filler) {
+            Some(v) => v,
+            None => return Err("filler must be in the grid"),
+        };
+
+        Ok(Self {
+            options,
+            plain,
+            upper: keysquare(upper, options.merge),
+            lower: keysquare(lower, options.merge),
+            filler,
+
+            pending: None,
+            offset: 0,
+            temp: Vec::new(),
+            trace: Trace::default(),
+        })
+    }
+
+    /// Unkeyed square, both upper left and lower right.
+    #[inline]
+    pub fn plain(&self) -> &Grid {
+        &self.plain
+    }
+
+    /// Keyed upper right square.
+    #[inline]
+    pub fn upper(&self) -> &Grid {
+        &self.upper
+    }
+
+    /// Keyed lower left square.
+    #[inline]
+    pub fn lower(&self) -> &Grid {
+        &self.lower
+    }
+
+    /// Whether the byte is encrypted, or dropped as a merged letter.
+    pub fn accepts(&self, byte: u8) -> bool {
+        let merge = self.options.merge;
+        merge.cell(&self.plain, byte).is_some() || merge.is_dropped(&self.plain, byte)
+    }
+
+    fn process(&mut self, byte: u8, encrypt: bool) -> Result<&[u8], CipherError> {
+        let offset = self.offset;
+        self.offset += 1;
+        self.temp.clear();
+
+        let grid = match (encrypt, self.pending) {
+            (true, _) => &self.plain,
+            (false, None) => &self.upper,
+            (false, Some(_)) => &self.lower,
+        };
+        let merge = self.options.merge;
+        let c = match merge.cell(grid, byte) {
+            Some(c) => c,
+            None if merge.is_dropped(grid, byte) => return Ok(&self.temp),
+            None => return Err(CipherError::invalid_byte("Four-Square", byte, offset)),
+        };
+
+        match self.pending.take() {
+            None => self.pending = Some(c),
+            Some(a) => {
+                let (a, b) = self.pair(a, c, encrypt);
+                self.temp.extend_from_slice(&[a, b]);
+            }
+        }
+        Ok(&self.temp)
+    }
+
+    fn pair(&mut self, a: usize, b: usize, encrypt: bool) -> (u8, u8) {
+        let cols = self.plain.cols();
+        let (ra, ca) = (a / cols, a % cols);
+        let (rb, cb) = (b / cols, b % cols);
+
+        let (input, out) = if encrypt {
+            (
+                [self.plain.symbol(ra, ca), self.plain.symbol(rb, cb)],
+                [self.upper.symbol(ra, cb), self.lower.symbol(rb, ca)],
+            )
+        } else {
+            (
+                [self.upper.symbol(ra, ca), self.lower.symbol(rb, cb)],
+                [self.plain.symbol(ra, cb), self.plain.symbol(rb, ca)],
+            )
+        };
+        self.trace.record(&input, &out, || {
+            format!(
+                "({}, {}) ({}, {}) -> ({}, {}) ({}, {})",
+                ra, ca, rb, cb, ra, cb, rb, ca
+            )
+        });
+        (out[0], out[1])
+    }
+}
+
+impl Encryptor for FourSquare {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte, true)
+    }
+
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        let Some(a) = self.pending.take() else {
+            return Ok(Vec::new());
+        };
+        let (a, b) = self.pair(a, self.filler, true);
+
+        Ok(vec![a, b])
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
+
+impl Decryptor for FourSquare {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte, false)
+    }
+
+    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        if self.pending.take().is_none() {
+            Ok(Vec::new())
+        } else {
+            Err(CipherError::incomplete_block("Four-Square", 2, self.offset))
+        }
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Merge;
+    use super::*;
+
+    fn encrypt(mut c: impl Encryptor, text: &[u8]) -> Result<String, CipherError> {
+        let mut ret = Vec::new();
+        for &b in text {
+            ret.extend_from_slice(c.encrypt_byte(b)?);
+        }
+        ret.extend(c.encrypt_finish()?);
+        Ok(String::from_utf8(ret).unwrap())
+    }
+
+    fn decrypt(mut c: impl Decryptor, text: &[u8]) -> Result<String, CipherError> {
+        let mut ret = Vec::new();
+        for &b in text {
+            ret.extend_from_slice(c.decrypt_byte(b)?);
+        }
+        ret.extend(c.decrypt_finish()?);
+        Ok(String::from_utf8(ret).unwrap())
+    }
+
+    fn options(filler: u8) -> PlayfairOptions {
+        PlayfairOptions {
+            filler,
+            merge: Merge::DropQ,
+            ..Default::default()
+        }
+    }
+
+    fn two_square(filler: u8) -> TwoSquare {
+        TwoSquare::with_options(b"EXAMPLE", b"KEYWORD", options(filler)).unwrap()
+    }
+
+    fn four_square(filler: u8) -> FourSquare {
+        FourSquare::with_options(b"EXAMPLE", b"KEYWORD", options(filler)).unwrap()
+    }
+
+    // Vectors from the Wikipedia articles of both ciphers
+    const PLAIN: &[u8] = b"HELPMEOBIWANKENOBI";
+
+    #[test]
+    fn two_square_vector() {
+        let cipher = "HEDLXWSDJYANHOTKDG";
+        assert_eq!(encrypt(two_square(b'X'), PLAIN).unwrap(), cipher);
+        assert_eq!(
+            decrypt(two_square(b'X'), cipher.as_bytes())
+                .unwrap()
+                .as_bytes(),
+            PLAIN
+        );
+    }
+
+    #[test]
+    fn four_square_vector() {
+        let cipher = "FYGMKYHOBXMFKKKIMD";
+        assert_eq!(encrypt(four_square(b'X'), PLAIN).unwrap(), cipher);
+        assert_eq!(
+            decrypt(four_square(b'X'), cipher.as_bytes())
+                .unwrap()
+                .as_bytes(),
+            PLAIN
+        );
+    }
+
+    #[test]
+    fn filler_pads_odd_length() {
+        let plain = &PLAIN[..17];
+        assert_eq!(
+            encrypt(two_square(b'X'), plain).unwrap(),
+            "HEDLXWSDJYANHOTKDU"
+        );
+        assert_eq!(
+            encrypt(two_square(b'Z'), plain).unwrap(),
+            "HEDLXWSDJYANHOTKFU"
+        );
+        assert_eq!(
+            encrypt(four_square(b'X'), plain).unwrap(),
+            "FYGMKYHOBXMFKKKIAU"
+        );
+        assert_eq!(
+            encrypt(four_square(b'Z'), plain).unwrap(),
+            "FYGMKYHOBXMFKKKIPU"
+        );
+    }
+
+    #[test]
+    fn filler_must_be_in_grid() {
+        assert!(TwoSquare::with_options(b"EXAMPLE", b"KEYWORD", options(b'Q')).is_err());
+        assert!(FourSquare::with_options(b"EXAMPLE", b"KEYWORD", options(b'1')).is_err());
+    }
+
+    #[test]
+    fn dropped_and_invalid() {
+        // Q is dropped from plaintext
+        assert_eq!(encrypt(four_square(b'X'), b"HQELP").unwrap(), "FYGM");
+        assert_eq!(
+            encrypt(two_square(b'X'), b"HE LP"),
+            Err(CipherError::invalid_byte("Two-Square", b' ', 2))
+        );
+        assert_eq!(
+            decrypt(four_square(b'X'), b"FYG"),
+            Err(CipherError::incomplete_block("Four-Square", 2, 3))
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use std::io::{self, Read, Write};
+
+use super::{CipherError, Decryptor, Encryptor};
+
+fn to_io_error(e: CipherError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Copy of a kept error, returned again by every later call.
+fn repeat_error(e: &io::Error) -> io::Error {
+    io::Error::new(e.kind(), e.to_string())
+}
+
+/// Passes bytes through the cipher into the writer, see [`Write::write`].
+///
+/// If it fails after some bytes are accepted, returns their count.
+/// The first error is kept and returned by every later call.
+fn write_through<W: Write, C>(
+    inner: &mut W,
+    cipher: &mut C,
+    error: &mut Option<io::Error>,
+    buf: &[u8],
+    f: impl for<'a> Fn(&'a mut C, u8) -> Result<&'a [u8], CipherError>,
+) -> io::Result<usize> {
+    if let Some(e) = error {
+        return Err(repeat_error(e));
+    }
+
+    for (i, &b) in buf.iter().enumerate() {
+        // Byte is consumed by the cipher even if the inner writer fails
+        let (n, e) = match f(cipher, b) {
+            Ok(v) => match inner.write_all(v) {
+                Ok(()) => continue,
+                Err(e) => (i + 1, e),
+            },
+            Err(e) => (i, to_io_error(e)),
+        };
+
+        let ret = if n == 0 { Err(repeat_error(&e)) } else { Ok(n) };
+        *error = Some(e);
+        return ret;
+    }
+    Ok(buf.len())
+}
+
+/// Writer that encrypts everything written into it.
+///
+/// Call [`finish`](Self::finish) after the last write, otherwise
+/// trailing block (eg. Hill padding) will not be written.
+#[derive(Debug)]
+pub struct EncryptWriter<W: Write, E: Encryptor> {
+    inner: W,
+    cipher: E,
+    /// First error, reported by every later call.
+    error: Option<io::Error>,
+}
+
+impl<W: Write, E: Encryptor> EncryptWriter<W, E> {
+    pub fn new(inner: W, cipher: E) -> Self {
+        Self {
+            inner,
+            cipher,
+            error: None,
+        }
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes remaining output and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let v = self.cipher.encrypt_finish().map_err(to_io_error)?;
+        self.inner.write_all(&v)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write, E: Encryptor> Write for EncryptWriter<W, E> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        write_through(
+            &mut self.inner,
+            &mut self.cipher,
+            &mut self.error,
+            buf,
+            E::encrypt_byte,
+        )
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writer that decrypts everything written into it.
+///
+/// Call [`finish`](Self::finish) after the last write, otherwise
+/// incomplete block will not be reported.
+#[derive(Debug)]
+pub struct DecryptWriter<W: Write, D: Decryptor> {
+    inner: W,
+    cipher: D,
+    /// First error, reported by every later call.
+    error: Option<io::Error>,
+}
+
+impl<W: Write, D: Decryptor> DecryptWriter<W, D> {
+    pub fn new(inner: W, cipher: D) -> Self {
+        Self {
+            inner,
+            cipher,
+            error: None,
+        }
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes remaining output and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let v = self.cipher.decrypt_finish().map_err(to_io_error)?;
+        self.inner.write_all(&v)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write, D: Decryptor> Write for DecryptWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        write_through(
+            &mut self.inner,
+            &mut self.cipher,
+            &mut self.error,
+            buf,
+            D::decrypt_byte,
+        )
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+const READ_CHUNK: usize = 4096;
+
+/// Reads from the inner reader through a cipher.
+#[derive(Debug)]
+struct CipherReader<R, C> {
+    inner: R,
+    cipher: C,
+
+    data: Vec<u8>,
+    pos: usize,
+    /// Set once the cipher is finished.
+    eof: bool,
+    /// First cipher error, reported by every call once the output before it is read.
+    error: Option<io::Error>,
+}
+
+impl<R: Read, C> CipherReader<R, C> {
+    fn new(inner: R, cipher: C) -> Self {
+        Self {
+            inner,
+            cipher,
+
+            data: Vec::new(),
+            pos: 0,
+            eof: false,
+            error: None,
+        }
+    }
+
+    fn read(
+        &mut self,
+        buf: &mut [u8],
+        f: impl for<'a> Fn(&'a mut C, u8) -> Result<&'a [u8], CipherError>,
+        finish: impl Fn(&mut C) -> Result<Vec<u8>, CipherError>,
+    ) -> io::Result<usize> {
+        let mut chunk = [0u8; READ_CHUNK];
+
+        while (self.pos >= self.data.len()) && !self.eof {
+            if let Some(e) = &self.error {
+                return Err(repeat_error(e));
+            }
+            self.data.clear();
+            self.pos = 0;
+
+            // Errors of the inner reader leave the cipher untouched, so they are not kept
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                match finish(&mut self.cipher) {
+                    Ok(v) => {
+                        self.data = v;
+                        self.eof = true;
+                    }
+                    Err(e) => self.error = Some(to_io_error(e)),
+                }
+                continue;
+            }
+
+            for &b in &chunk[..n] {
+                match f(&mut self.cipher, b) {
+                    Ok(v) => self.data.extend_from_slice(v),
+                    Err(e) => {
+                        self.error = Some(to_io_error(e));
+                        break;
+                    }
+                }
+            }
+        }
+
+        let n = buf.len().min(self.data.len() - self.pos);
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Reader that encrypts bytes read from the inner reader.
+#[derive(Debug)]
+pub struct EncryptReader<R: Read, E: Encryptor>(CipherReader<R, E>);
+
+impl<R: Read, E: Encryptor> EncryptReader<R, E> {
+    pub fn new(inner: R, cipher: E) -> Self {
+        Self(CipherReader::new(inner, cipher))
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.0.inner
+    }
+}
+
+impl<R: Read, E: Encryptor> Read for EncryptReader<R, E> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf, E::encrypt_byte, E::encrypt_finish)
+    }
+}
+
+/// Reader that decrypts bytes read from the inner reader.
+#[derive(Debug)]
+pub struct DecryptReader<R: Read, D: Decryptor>(CipherReader<R, D>);
+
+impl<R: Read, D: Decryptor> DecryptReader<R, D> {
+    pub fn new(inner: R, cipher: D) -> Self {
+        Self(CipherReader::new(inner, cipher))
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.0.inner
+    }
+}
+
+impl<R: Read, D: Decryptor> Read for DecryptReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf, D::decrypt_byte, D::decrypt_finish)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Hill, Vignere};
+    use super::*;
+
+    /// Writer failing on every write.
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn vigenere() -> Vignere {
+        Vignere::new(b"KEY").unwrap()
+    }
+
+    fn hill() -> Hill {
+        Hill::new(&[5, 2, 3, 7]).unwrap()
+    }
+
+    #[test]
+    fn writer_round_trip() {
+        let mut w = EncryptWriter::new(Vec::new(), hill());
+        w.write_all(b"HEY").unwrap();
+        assert_eq!(w.finish().unwrap(), b"RXQU");
+
+        let mut w = DecryptWriter::new(Vec::new(), hill());
+        w.write_all(b"RXQU").unwrap();
+        assert_eq!(w.finish().unwrap(), b"HEYA");
+    }
+
+    #[test]
+    fn writer_partial_write_then_error() {
+        let mut w = EncryptWriter::new(Vec::new(), vigenere());
+        assert_eq!(w.write(b"AB1C").unwrap(), 2);
+        assert_eq!(w.get_ref(), b"KF");
+
+        // Later bytes are not passed to the cipher
+        for _ in 0..2 {
+            let e = w.write(b"C").unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        }
+        assert_eq!(w.get_ref(), b"KF");
+        assert!(w.finish().is_err());
+    }
+
+    #[test]
+    fn writer_inner_error() {
+        // First byte is consumed by the cipher before the inner writer fails
+        let mut w = EncryptWriter::new(Broken, vigenere());
+        assert_eq!(w.write(b"AB").unwrap(), 1);
+        for _ in 0..2 {
+            assert_eq!(w.write(b"B").unwrap_err().kind(), io::ErrorKind::Other);
+        }
+        assert!(w.finish().is_err());
+    }
+
+    #[test]
+    fn writer_incomplete_block() {
+        let mut w = DecryptWriter::new(Vec::new(), hill());
+        w.write_all(b"RXQ").unwrap();
+        assert_eq!(w.get_ref(), b"HE");
+        let e = w.finish().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reader_round_trip() {
+        let mut out = Vec::new();
+        EncryptReader::new(&b"HEY"[..], hill())
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"RXQU");
+
+        let mut out = Vec::new();
+        DecryptReader::new(&b"RXQU"[..], hill())
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"HEYA");
+    }
+
+    #[test]
+    fn reader_error_after_output() {
+        let mut r = EncryptReader::new(&b"AB1C"[..], vigenere());
+        let mut buf = [0; 16];
+        assert_eq!(r.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"KF");
+        for _ in 0..2 {
+            let e = r.read(&mut buf).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn reader_incomplete_block() {
+        let mut r = DecryptReader::new(&b"RXQ"[..], hill());
+        let mut buf = [0; 16];
+        assert_eq!(r.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"HE");
+
+        // Finish is not run again after failing
+        for _ in 0..2 {
+            let e = r.read(&mut buf).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use std::slice;
+
+use super::{empty_slice, CipherError, Decryptor, Encryptor};
+
+/// Radix encoding parameters.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Radix {
+    name: &'static str,
+    /// Bits per symbol.
+    bits: u32,
+    symbols: &'static [u8],
+    /// Pad with `=` to whole groups.
+    padding: bool,
+    ignore_case: bool,
+}
+
+impl Radix {
+    /// Symbols per group of whole bytes.
+    fn group(&self) -> usize {
+        let mut n = 1;
+        while !(n * self.bits).is_multiple_of(8) {
+            n += 1;
+        }
+        n as _
+    }
+
+    fn value(&self, byte: u8) -> Option<u32> {
+        let byte = if self.ignore_case {
+            byte.to_ascii_uppercase()
+        } else {
+            byte
+        };
+        self.symbols.iter().position(|&v| v == byte).map(|v| v as _)
+    }
+}
+
+const PAD: u8 = b'=';
+
+const HEX: Radix = Radix {
+    name: "Hex",
+    bits: 4,
+    symbols: b"0123456789ABCDEF",
+    padding: false,
+    ignore_case: true,
+};
+
+const BASE32: Radix = Radix {
+    name: "Base32",
+    bits: 5,
+    symbols: b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+    padding: true,
+    ignore_case: true,
+};
+
+const BASE64: Radix = Radix {
+    name: "Base64",
+    bits: 6,
+    symbols: b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+    padding: true,
+    ignore_case: false,
+};
+
+/// Bytes into symbols.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Encoder {
+    radix: &'static Radix,
+
+    acc: u32,
+    pending: u32,
+    count: usize,
+    temp: [u8; 2],
+}
+
+impl Encoder {
+    fn new(radix: &'static Radix) -> Self {
+        Self {
+            radix,
+
+            acc: 0,
+            pending: 0,
+            count: 0,
+            temp: [0; 2],
+        }
+    }
+
+    fn symbol(&self, shift: u32) -> u8 {
+        self.radix.symbols[((self.acc >> shift) & ((1 << self.radix.bits) - 1)) as usize]
+    }
+
+    fn encode_byte(&mut self, byte: u8) -> &[u8] {
+        let bits = self.radix.bits;
+        self.acc = (self.acc << 8) | byte as u32;
+        self.pending += 8;
+
+        let mut n = 0;
+        while self.pending >= bits {
+            self.pending -= bits;
+            self.temp[n] = self.symbol(self.pending);
+            n += 1;
+        }
+        self.acc &= (1 << self.pending) - 1;
+        self.count += n;
+
+        &self.temp[..n]
+    }
+
+    fn encode_finish(&mut self) -> Vec<u8> {
+        let mut ret = Vec::new();
+        if self.pending > 0 {
+            self.acc <<= self.radix.bits - self.pending;
+            ret.push(self.symbol(0));
+            self.count += 1;
+        }
+        if self.radix.padding {
+            let group = self.radix.group();
+            while !self.count.is_multiple_of(group) {
+                ret.push(PAD);
+                self.count += 1;
+            }
+        }
+
+        (self.acc, self.pending, self.count) = (0, 0, 0);
+        ret
+    }
+}
+
+/// Symbols into bytes, whitespace is skipped.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Decoder {
+    radix: &'static Radix,
+
+    acc: u32,
+    pending: u32,
+    /// Symbols and padding read.
+    count: usize,
+    padded: bool,
+    temp: u8,
+}
+
+impl Decoder {
+    fn new(radix: &'static Radix) -> Self {
+        Self {
+            radix,
+
+            acc: 0,
+            pending: 0,
+            count: 0,
+            padded: false,
+            temp: 0,
+        }
+    }
+
+    fn decode_byte(&mut self, byte: u8, offset: usize) -> Result<&[u8], CipherError> {
+        if byte.is_ascii_whitespace() {
+            return Ok(empty_slice());
+        }
+        if self.radix.padding && (byte == PAD) {
+            self.padded = true;
+            self.count += 1;
+            return Ok(empty_slice());
+        }
+
+        let v = match self.radix.value(byte) {
+            Some(v) if !self.padded => v,
+            _ => return Err(CipherError::invalid_byte(self.radix.name, byte, offset)),
+        };
+        self.count += 1;
+        self.acc = (self.acc << self.radix.bits) | v;
+        self.pending += self.radix.bits;
+
+        if self.pending < 8 {
+            return Ok(empty_slice());
+        }
+        self.pending -= 8;
+        self.temp = (self.acc >> self.pending) as u8;
+        self.acc &= (1 << self.pending) - 1;
+
+        Ok(slice::from_ref(&self.temp))
+    }
+
+    fn decode_finish(&mut self, offset: usize) -> Result<Vec<u8>, CipherError> {
+        let group = self.radix.group();
+        // Leftover bits must be padding of the last byte, padding must fill a group
+        let incomplete =
+            (self.pending >= self.radix.bits) || (self.padded && !self.count.is_multiple_of(group));
+
+        (self.acc, self.pending, self.count, self.padded) = (0, 0, 0, false);
+        if incomplete {
+            Err(CipherError::incomplete_block(
+                self.radix.name,
+                group,
+                offset,
+            ))
+        } else {
+            Ok(Vec::new())
+        }
+    }
+}
+
+macro_rules! transport {
+    ($(#[$encode_meta:meta])* $encode:ident, $(#[$decode_meta:meta])* $decode:ident, $radix:ident) => {
+        $(#[$encode_meta])*
+        ///
+        /// Decrypting decodes it back.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $encode {
+            encoder: Encoder,
+            decoder: Decoder,
+            offset: usize,
+        }
+
+        impl $encode {
+            pub fn new() -> Self {
+                Self {
+                    encoder: Encoder::new(&$radix),
+                    decoder: Decoder::new(&$radix),
+                    offset: 0,
+                }
+            }
+        }
+
+        impl Default for $encode {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl Encryptor for $encode {
+            fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+                Ok(self.encoder.encode_byte(byte))
+            }
+
+            fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+                Ok(self.encoder.encode_finish())
+            }
+        }
+
+        impl Decryptor for $encode {
+            fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+                let offset = self.offset;
+                self.offset += 1;
+                self.decoder.decode_byte(byte, offset)
+            }
+
+            fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+                let offset = self.offset;
+                self.offset = 0;
+                self.decoder.decode_finish(offset)
+            }
+        }
+
+        $(#[$decode_meta])*
+        ///
+        /// Decrypting encodes it back.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+        pub struct $decode($encode);
+
+        impl $decode {
+            pub fn new() -> Self {
+                Self($encode::new())
+            }
+        }
+
+        impl Encryptor for $decode {
+            fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+                self.0.decrypt_byte(byte)
+            }
+
+            fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+                self.0.decrypt_finish()
+            }
+        }
+
+        impl Decryptor for $decode {
+            fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+                self.0.encrypt_byte(byte)
+            }
+
+            fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+                self.0.encrypt_finish()
+            }
+        }
+    };
+}
+
+transport!(
+    /// Encrypting encodes bytes as uppercase hex digits.
+    HexEncode,
+    /// Encrypting decodes hex digits (case insensitive) into bytes.
+    HexDecode,
+    HEX
+);
+
+transport!(
+    /// Encrypting encodes bytes as padded Base32 (RFC 4648).
+    Base32Encode,
+    /// Encrypting decodes Base32 (RFC 4648) into bytes, padding is optional.
+    Base32Decode,
+    BASE32
+);
+
+transport!(
+    /// Encrypting encodes bytes as padded Base64 (RFC 4648).
+    Base64Encode,
+    /// Encrypting decodes Base64 (RFC 4648) into bytes, padding is optional.
+    Base64Decode,
+    BASE64
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(mut c: impl Encryptor, data: &[u8]) -> String {
+        let mut ret = Vec::new();
+        for &b in data {
+            ret.extend_from_slice(c.encrypt_byte(b).unwrap());
+        }
+        ret.extend(c.encrypt_finish().unwrap());
+        String::from_utf8(ret).unwrap()
+    }
+
+    fn decode(mut c: impl Decryptor, text: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let mut ret = Vec::new();
+        for &b in text {
+            ret.extend_from_slice(c.decrypt_byte(b)?);
+        }
+        ret.extend(c.decrypt_finish()?);
+        Ok(ret)
+    }
+
+    /// RFC 4648 test vectors, in order of hex, Base32 and Base64.
+    const VECTORS: &[(&str, &str, &str, &str)] = &[
+        ("", "", "", ""),
+        ("f", "66", "MY======", "Zg=="),
+        ("fo", "666F", "MZXQ====", "Zm8="),
+        ("foo", "666F6F", "MZXW6===", "Zm9v"),
+        ("foob", "666F6F62", "MZXW6YQ=", "Zm9vYg=="),
+        ("fooba", "666F6F6261", "MZXW6YTB", "Zm9vYmE="),
+        ("foobar", "666F6F626172", "MZXW6YTBOI======", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn rfc4648_vectors() {
+        for &(data, hex, base32, base64) in VECTORS {
+            let data = data.as_bytes();
+            assert_eq!(encode(HexEncode::new(), data), hex);
+            assert_eq!(encode(Base32Encode::new(), data), base32);
+            assert_eq!(encode(Base64Encode::new(), data), base64);
+
+            assert_eq!(decode(HexEncode::new(), hex.as_bytes()).unwrap(), data);
+            assert_eq!(
+                decode(Base32Encode::new(), base32.as_bytes()).unwrap(),
+                data
+            );
+            assert_eq!(
+                decode(Base64Encode::new(), base64.as_bytes()).unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn decoder_types_swap_directions() {
+        assert_eq!(encode(Base64Decode::new(), b"Zm9vYmE="), "fooba");
+        assert_eq!(decode(Base64Decode::new(), b"fooba").unwrap(), b"Zm9vYmE=");
+    }
+
+    #[test]
+    fn decode_without_padding_and_whitespace() {
+        assert_eq!(decode(HexEncode::new(), b"66 6f\n6F").unwrap(), b"foo");
+        assert_eq!(
+            decode(Base32Encode::new(), b"mzxw6\nytb").unwrap(),
+            b"fooba"
+        );
+        assert_eq!(decode(Base64Encode::new(), b"Zm9v\nYg").unwrap(), b"foob");
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(
+            decode(HexEncode::new(), b"66G6"),
+            Err(CipherError::invalid_byte("Hex", b'G', 2))
+        );
+        assert_eq!(
+            decode(Base64Encode::new(), b"Zm9v!"),
+            Err(CipherError::invalid_byte("Base64", b'!', 4))
+        );
+        // Base64 is case sensitive, Base32 is not
+        assert_eq!(decode(Base64Encode::new(), b"zg==").unwrap(), b"\xce");
+        assert_eq!(decode(Base32Encode::new(), b"my======").unwrap(), b"f");
+        // Nothing may follow padding
+        assert_eq!(
+            decode(Base64Encode::new(), b"Zg==Zg"),
+            Err(CipherError::invalid_byte("Base64", b'Z', 4))
+        );
+    }
+
+    #[test]
+    fn decode_truncated() {
+        assert_eq!(
+            decode(HexEncode::new(), b"666"),
+            Err(CipherError::incomplete_block("Hex", 2, 3))
+        );
+        assert_eq!(
+            decode(Base32Encode::new(), b"MZXW6Y"),
+            Err(CipherError::incomplete_block("Base32", 8, 6))
+        );
+        assert_eq!(
+            decode(Base64Encode::new(), b"Zm9vY"),
+            Err(CipherError::incomplete_block("Base64", 4, 5))
+        );
+        // Padding must fill the group
+        assert_eq!(
+            decode(Base64Encode::new(), b"Zg="),
+            Err(CipherError::incomplete_block("Base64", 4, 3))
+        );
+    }
+}
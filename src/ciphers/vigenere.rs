@@ -1,36 +1,38 @@
 use std::mem;
 use std::slice;
 
-use super::{CipherError, Decryptor, Encryptor};
+use crate::util::Zn;
 
-const A_UPPER: u8 = 'A' as _;
-const Z_UPPER: u8 = 'Z' as _;
-const A_LOWER: u8 = 'a' as _;
-const Z_LOWER: u8 = 'z' as _;
+use super::{describe, Alphabet, CipherError, Decryptor, Encryptor, Trace, TraceStep};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Vignere {
+    alphabet: Alphabet,
     key: Vec<u8>,
 
     count: usize,
-    temp: [u8; 2],
+    temp: u8,
+    trace: Trace,
 }
 
 impl Vignere {
     pub fn new(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin())
+    }
+
+    /// Key symbols outside the alphabet are skipped.
+    pub fn with_alphabet(key: &[u8], alphabet: Alphabet) -> Result<Self, &'static str> {
         if key.len() == 0 {
             return Err("key cannot be empty");
         }
 
         Ok(Self {
-            key: Vec::from_iter(key.iter().filter_map(|&b| match b {
-                A_UPPER..=Z_UPPER => Some(b - A_UPPER),
-                A_LOWER..=Z_LOWER => Some(b - A_LOWER),
-                _ => None,
-            })),
+            key: Vec::from_iter(key.iter().filter_map(|&b| alphabet.index(b))),
+            alphabet,
 
             count: 0,
-            temp: <_>::default(),
+            temp: 0,
+            trace: Trace::default(),
         })
     }
 }
@@ -38,59 +40,87 @@ impl Vignere {
 impl Encryptor for Vignere {
     fn encrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
         if self.key.is_empty() {
-            return Err(CipherError::new());
+            return Err(CipherError::empty_key("Vigenere", self.count));
         }
 
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
+        byte = match self.alphabet.index(byte) {
+            Some(v) => v,
+            None => return Err(CipherError::invalid_byte("Vigenere", byte, self.count)),
         };
-        byte = ((byte + self.key[self.count % self.key.len()]) % 26) + A_UPPER;
+        let n = self.alphabet.len();
+        let key = self.key[self.count % self.key.len()];
+        let p = byte;
+        byte = ((p as usize + key as usize) % n) as _;
         self.count += 1;
 
-        self.temp[1] = byte;
-
-        let nl: u8 = if (self.count / 5) % 12 == 0 {
-            '\n' as _
-        } else {
-            ' ' as _
-        };
-        match self.count % 5 {
-            1 if self.count != 1 => {
-                self.temp[0] = nl;
-                Ok(&self.temp)
-            }
-            _ => Ok(&self.temp[1..]),
-        }
+        self.temp = self.alphabet.symbol(byte);
+        let alphabet = &self.alphabet;
+        self.trace.record(&[alphabet.symbol(p)], &[self.temp], || {
+            format!(
+                "{} + {} = {} mod {}",
+                describe(alphabet.symbol(p), p),
+                describe(alphabet.symbol(key), key),
+                describe(alphabet.symbol(byte), byte),
+                n
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
 
 impl Decryptor for Vignere {
     fn decrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
         if self.key.is_empty() {
-            return Err(CipherError::new());
+            return Err(CipherError::empty_key("Vigenere", self.count));
         }
 
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
+        byte = match self.alphabet.index(byte) {
+            Some(v) => v,
+            None => return Err(CipherError::invalid_byte("Vigenere", byte, self.count)),
         };
-        byte = ((byte + 26 - self.key[self.count % self.key.len()]) % 26) + A_UPPER;
+        let n = self.alphabet.len();
+        let key = self.key[self.count % self.key.len()];
+        let c = byte;
+        byte = ((c as usize + n - key as usize) % n) as _;
         self.count += 1;
 
-        self.temp[0] = byte;
-        Ok(&self.temp[..1])
+        self.temp = self.alphabet.symbol(byte);
+        let alphabet = &self.alphabet;
+        self.trace.record(&[alphabet.symbol(c)], &[self.temp], || {
+            format!(
+                "{} - {} = {} mod {}",
+                describe(alphabet.symbol(c), c),
+                describe(alphabet.symbol(key), key),
+                describe(alphabet.symbol(byte), byte),
+                n
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -99,6 +129,7 @@ pub struct Vignere256 {
 
     offset: usize,
     temp: u8,
+    trace: Trace,
 }
 
 impl Vignere256 {
@@ -111,6 +142,7 @@ impl Vignere256 {
             key: Vec::from(key),
             offset: 0,
             temp: 0,
+            trace: Trace::default(),
         })
     }
 }
@@ -118,60 +150,91 @@ impl Vignere256 {
 impl Encryptor for Vignere256 {
     fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
         if self.key.is_empty() {
-            return Err(CipherError::new());
+            return Err(CipherError::empty_key("Vigenere (8-bit)", self.offset));
         }
 
-        self.temp = byte.wrapping_add(self.key[self.offset % self.key.len()]);
+        let key = self.key[self.offset % self.key.len()];
+        self.temp = byte.wrapping_add(key);
         self.offset += 1;
 
+        let out = self.temp;
+        self.trace.record(&[byte], &[out], || {
+            format!("{:#04x} + {:#04x} = {:#04x} mod 256", byte, key, out)
+        });
         Ok(slice::from_ref(&self.temp))
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
 
 impl Decryptor for Vignere256 {
     fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
         if self.key.is_empty() {
-            return Err(CipherError::new());
+            return Err(CipherError::empty_key("Vigenere (8-bit)", self.offset));
         }
 
-        self.temp = byte.wrapping_sub(self.key[self.offset % self.key.len()]);
+        let key = self.key[self.offset % self.key.len()];
+        self.temp = byte.wrapping_sub(key);
         self.offset += 1;
 
+        let out = self.temp;
+        self.trace.record(&[byte], &[out], || {
+            format!("{:#04x} - {:#04x} = {:#04x} mod 256", byte, key, out)
+        });
         Ok(slice::from_ref(&self.temp))
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VignereAutokey {
+    alphabet: Alphabet,
     key: Vec<u8>,
 
     count: usize,
-    temp: [u8; 2],
+    temp: u8,
+    trace: Trace,
 }
 
 impl VignereAutokey {
     pub fn new(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin())
+    }
+
+    /// Key symbols outside the alphabet are skipped.
+    pub fn with_alphabet(key: &[u8], alphabet: Alphabet) -> Result<Self, &'static str> {
         if key.len() == 0 {
             return Err("key cannot be empty");
         }
 
         Ok(Self {
-            key: Vec::from_iter(key.iter().filter_map(|&b| match b {
-                A_UPPER..=Z_UPPER => Some(b - A_UPPER),
-                A_LOWER..=Z_LOWER => Some(b - A_LOWER),
-                _ => None,
-            })),
+            key: Vec::from_iter(key.iter().filter_map(|&b| alphabet.index(b))),
+            alphabet,
 
             count: 0,
-            temp: <_>::default(),
+            temp: 0,
+            trace: Trace::default(),
         })
     }
 }
@@ -179,63 +242,492 @@ impl VignereAutokey {
 impl Encryptor for VignereAutokey {
     fn encrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
         if self.key.is_empty() {
-            return Err(CipherError::new());
+            return Err(CipherError::empty_key("Vigenere (Autokey)", self.count));
         }
 
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
+        byte = match self.alphabet.index(byte) {
+            Some(v) => v,
+            None => {
+                return Err(CipherError::invalid_byte(
+                    "Vigenere (Autokey)",
+                    byte,
+                    self.count,
+                ))
+            }
         };
         let ix = self.count % self.key.len();
         let key = mem::replace(&mut self.key[ix], byte);
-        byte = ((byte + key) % 26) + A_UPPER;
+        let n = self.alphabet.len();
+        let p = byte;
+        byte = ((p as usize + key as usize) % n) as _;
         self.count += 1;
 
-        self.temp[1] = byte;
-
-        let nl: u8 = if (self.count / 5) % 12 == 0 {
-            '\n' as _
-        } else {
-            ' ' as _
-        };
-        match self.count % 5 {
-            1 if self.count != 1 => {
-                self.temp[0] = nl;
-                Ok(&self.temp)
-            }
-            _ => Ok(&self.temp[1..]),
-        }
+        self.temp = self.alphabet.symbol(byte);
+        let alphabet = &self.alphabet;
+        self.trace.record(&[alphabet.symbol(p)], &[self.temp], || {
+            format!(
+                "{} + {} = {} mod {}",
+                describe(alphabet.symbol(p), p),
+                describe(alphabet.symbol(key), key),
+                describe(alphabet.symbol(byte), byte),
+                n
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
     }
 
     fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
 
 impl Decryptor for VignereAutokey {
     fn decrypt_byte(&mut self, mut byte: u8) -> Result<&[u8], CipherError> {
         if self.key.is_empty() {
-            return Err(CipherError::new());
+            return Err(CipherError::empty_key("Vigenere (Autokey)", self.count));
+        }
+
+        byte = match self.alphabet.index(byte) {
+            Some(v) => v,
+            None => {
+                return Err(CipherError::invalid_byte(
+                    "Vigenere (Autokey)",
+                    byte,
+                    self.count,
+                ))
+            }
+        };
+        let ix = self.count % self.key.len();
+        let n = self.alphabet.len();
+        let c = byte;
+        byte = ((c as usize + n - self.key[ix] as usize) % n) as _;
+        let key = mem::replace(&mut self.key[ix], byte);
+        self.count += 1;
+
+        self.temp = self.alphabet.symbol(byte);
+        let alphabet = &self.alphabet;
+        self.trace.record(&[alphabet.symbol(c)], &[self.temp], || {
+            format!(
+                "{} - {} = {} mod {}",
+                describe(alphabet.symbol(c), c),
+                describe(alphabet.symbol(key), key),
+                describe(alphabet.symbol(byte), byte),
+                n
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
+    }
+
+    fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        Ok(Vec::new())
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
+
+/// Symbol operation of a tableau cipher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tableau {
+    /// `c = p + k`
+    Vigenere,
+    /// `c = k - p`
+    Beaufort,
+    /// `c = p - k`
+    VariantBeaufort,
+}
+
+impl Tableau {
+    fn encrypt(self, p: Zn, k: Zn) -> Zn {
+        match self {
+            Self::Vigenere => p + k,
+            Self::Beaufort => k - p,
+            Self::VariantBeaufort => p - k,
+        }
+    }
+
+    fn decrypt(self, c: Zn, k: Zn) -> Zn {
+        match self {
+            Self::Vigenere => c - k,
+            Self::Beaufort => k - c,
+            Self::VariantBeaufort => c + k,
+        }
+    }
+
+    /// Trace detail of input `a` with key `k`.
+    fn detail(self, encrypt: bool, a: String, k: String, out: String, modulus: u32) -> String {
+        match (self, encrypt) {
+            (Self::Vigenere, true) | (Self::VariantBeaufort, false) => {
+                format!("{} + {} = {} mod {}", a, k, out, modulus)
+            }
+            (Self::Vigenere, false) | (Self::VariantBeaufort, true) => {
+                format!("{} - {} = {} mod {}", a, k, out, modulus)
+            }
+            (Self::Beaufort, _) => format!("{} - {} = {} mod {}", k, a, out, modulus),
         }
+    }
+}
+
+/// Periodic tableau cipher with optional ciphertext autokey.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Periodic {
+    name: &'static str,
+    tableau: Tableau,
+    alphabet: Alphabet,
+    key: Vec<Zn>,
+    /// Ciphertext replaces used key symbol.
+    autokey: bool,
+    /// Key is shown as shifts instead of symbols.
+    numeric: bool,
+
+    count: usize,
+    temp: u8,
+    trace: Trace,
+}
+
+impl Periodic {
+    /// Key values are reduced modulo the alphabet length.
+    fn new(
+        name: &'static str,
+        tableau: Tableau,
+        alphabet: Alphabet,
+        key: impl IntoIterator<Item = u8>,
+        autokey: bool,
+        numeric: bool,
+    ) -> Result<Self, &'static str> {
+        let n = alphabet.len() as u32;
+        let key = Vec::from_iter(key.into_iter().map(|v| Zn::new(v as _, n)));
+        if key.is_empty() {
+            return Err("key cannot be empty");
+        }
+
+        Ok(Self {
+            name,
+            tableau,
+            alphabet,
+            key,
+            autokey,
+            numeric,
+
+            count: 0,
+            temp: 0,
+            trace: Trace::default(),
+        })
+    }
 
-        byte = match byte {
-            v @ A_UPPER..=Z_UPPER => v - A_UPPER,
-            v @ A_LOWER..=Z_LOWER => v - A_LOWER,
-            _ => return Err(CipherError::new()),
+    fn process(&mut self, byte: u8, encrypt: bool) -> Result<&[u8], CipherError> {
+        let Some(v) = self.alphabet.index(byte) else {
+            return Err(CipherError::invalid_byte(self.name, byte, self.count));
         };
+        let n = self.alphabet.len() as u32;
         let ix = self.count % self.key.len();
-        let key = &mut self.key[ix];
-        byte = (byte + 26 - *key) % 26;
-        *key = byte;
-        byte += A_UPPER;
+        let (input, key) = (Zn::new(v as _, n), self.key[ix]);
+        let (out, cipher) = if encrypt {
+            let c = self.tableau.encrypt(input, key);
+            (c, c)
+        } else {
+            (self.tableau.decrypt(input, key), input)
+        };
+        if self.autokey {
+            self.key[ix] = cipher;
+        }
+        self.count += 1;
+
+        let out = u32::from(out) as u8;
+        self.temp = self.alphabet.symbol(out);
+        let (tableau, alphabet, numeric) = (self.tableau, &self.alphabet, self.numeric);
+        self.trace.record(&[alphabet.symbol(v)], &[self.temp], || {
+            let k = key.value() as u8;
+            tableau.detail(
+                encrypt,
+                describe(alphabet.symbol(v), v),
+                if numeric {
+                    k.to_string()
+                } else {
+                    describe(alphabet.symbol(k), k)
+                },
+                describe(alphabet.symbol(out), out),
+                n,
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
+    }
+}
+
+macro_rules! periodic_cipher {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $name(Periodic);
+
+        impl Encryptor for $name {
+            fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+                self.0.process(byte, true)
+            }
+
+            fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+                Ok(Vec::new())
+            }
+
+            fn set_tracing(&mut self, enabled: bool) {
+                self.0.trace.set_enabled(enabled)
+            }
+
+            fn take_trace(&mut self) -> Vec<TraceStep> {
+                self.0.trace.take()
+            }
+        }
+
+        impl Decryptor for $name {
+            fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+                self.0.process(byte, false)
+            }
+
+            fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+                Ok(Vec::new())
+            }
+
+            fn set_tracing(&mut self, enabled: bool) {
+                self.0.trace.set_enabled(enabled)
+            }
+
+            fn take_trace(&mut self) -> Vec<TraceStep> {
+                self.0.trace.take()
+            }
+        }
+    };
+}
+
+periodic_cipher!(
+    /// Beaufort cipher, `c = k - p`. Encryption and decryption are the same.
+    Beaufort
+);
+
+periodic_cipher!(
+    /// Variant Beaufort cipher, `c = p - k`. Same as decrypting Vigenere.
+    VariantBeaufort
+);
+
+periodic_cipher!(
+    /// Gronsfeld cipher, Vigenere with a numeric key.
+    Gronsfeld
+);
+
+impl Beaufort {
+    pub fn new(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin(), false)
+    }
+
+    /// Beaufort with key extended by the ciphertext.
+    pub fn new_autokey(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin(), true)
+    }
+
+    /// Key symbols outside the alphabet are skipped.
+    pub fn with_alphabet(
+        key: &[u8],
+        alphabet: Alphabet,
+        autokey: bool,
+    ) -> Result<Self, &'static str> {
+        let name = if autokey {
+            "Beaufort (Autokey)"
+        } else {
+            "Beaufort"
+        };
+        let key = Vec::from_iter(key.iter().filter_map(|&b| alphabet.index(b)));
+
+        Ok(Self(Periodic::new(
+            name,
+            Tableau::Beaufort,
+            alphabet,
+            key,
+            autokey,
+            false,
+        )?))
+    }
+}
+
+impl VariantBeaufort {
+    pub fn new(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin(), false)
+    }
+
+    /// Variant Beaufort with key extended by the ciphertext.
+    pub fn new_autokey(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin(), true)
+    }
+
+    /// Key symbols outside the alphabet are skipped.
+    pub fn with_alphabet(
+        key: &[u8],
+        alphabet: Alphabet,
+        autokey: bool,
+    ) -> Result<Self, &'static str> {
+        let name = if autokey {
+            "Variant Beaufort (Autokey)"
+        } else {
+            "Variant Beaufort"
+        };
+        let key = Vec::from_iter(key.iter().filter_map(|&b| alphabet.index(b)));
+
+        Ok(Self(Periodic::new(
+            name,
+            Tableau::VariantBeaufort,
+            alphabet,
+            key,
+            autokey,
+            false,
+        )?))
+    }
+}
+
+impl Gronsfeld {
+    /// Key digits are shifts, other characters are ignored.
+    pub fn new(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin(), false)
+    }
+
+    /// Gronsfeld with key extended by the ciphertext.
+    pub fn new_autokey(key: &[u8]) -> Result<Self, &'static str> {
+        Self::with_alphabet(key, Alphabet::latin(), true)
+    }
+
+    /// Key digits are shifts, other characters are ignored.
+    pub fn with_alphabet(
+        key: &[u8],
+        alphabet: Alphabet,
+        autokey: bool,
+    ) -> Result<Self, &'static str> {
+        let name = if autokey {
+            "Gronsfeld (Autokey)"
+        } else {
+            "Gronsfeld"
+        };
+        let key = key.iter().filter(|b| b.is_ascii_digit()).map(|b| b - b'0');
+
+        Ok(Self(Periodic::new(
+            name,
+            Tableau::Vigenere,
+            alphabet,
+            key,
+            autokey,
+            true,
+        )?))
+    }
+}
+
+/// Vigenere cipher taking one key symbol per input symbol from a key stream.
+///
+/// Key stream bytes outside the alphabet are skipped.
+#[derive(Debug, Clone)]
+pub struct RunningKey<K> {
+    alphabet: Alphabet,
+    key: K,
+
+    count: usize,
+    temp: u8,
+    trace: Trace,
+}
+
+impl<K: Iterator<Item = u8>> RunningKey<K> {
+    pub fn new(key: impl IntoIterator<IntoIter = K>) -> Self {
+        Self::with_alphabet(key, Alphabet::latin())
+    }
+
+    pub fn with_alphabet(key: impl IntoIterator<IntoIter = K>, alphabet: Alphabet) -> Self {
+        Self {
+            alphabet,
+            key: key.into_iter(),
+
+            count: 0,
+            temp: 0,
+            trace: Trace::default(),
+        }
+    }
+
+    fn next_key(&mut self) -> Result<u8, CipherError> {
+        let alphabet = &self.alphabet;
+        self.key
+            .find_map(|v| alphabet.index(v))
+            .ok_or_else(|| CipherError::key_exhausted("Running Key", self.count))
+    }
+
+    fn process(&mut self, byte: u8, encrypt: bool) -> Result<&[u8], CipherError> {
+        let Some(v) = self.alphabet.index(byte) else {
+            return Err(CipherError::invalid_byte("Running Key", byte, self.count));
+        };
+        let k = self.next_key()?;
         self.count += 1;
 
-        self.temp[0] = byte;
-        Ok(&self.temp[..1])
+        let n = self.alphabet.len() as u32;
+        let (input, key) = (Zn::new(v as _, n), Zn::new(k as _, n));
+        let out = if encrypt {
+            Tableau::Vigenere.encrypt(input, key)
+        } else {
+            Tableau::Vigenere.decrypt(input, key)
+        };
+
+        let out = u32::from(out) as u8;
+        self.temp = self.alphabet.symbol(out);
+        let alphabet = &self.alphabet;
+        self.trace.record(&[alphabet.symbol(v)], &[self.temp], || {
+            Tableau::Vigenere.detail(
+                encrypt,
+                describe(alphabet.symbol(v), v),
+                describe(alphabet.symbol(k), k),
+                describe(alphabet.symbol(out), out),
+                n,
+            )
+        });
+        Ok(slice::from_ref(&self.temp))
+    }
+}
+
+impl<K: Iterator<Item = u8>> Encryptor for RunningKey<K> {
+    fn encrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte, true)
+    }
+
+    fn encrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
+        Ok(Vec::new())
+    }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
+}
+
+impl<K: Iterator<Item = u8>> Decryptor for RunningKey<K> {
+    fn decrypt_byte(&mut self, byte: u8) -> Result<&[u8], CipherError> {
+        self.process(byte, false)
     }
 
     fn decrypt_finish(&mut self) -> Result<Vec<u8>, CipherError> {
         Ok(Vec::new())
     }
+
+    fn set_tracing(&mut self, enabled: bool) {
+        self.trace.set_enabled(enabled)
+    }
+
+    fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.trace.take()
+    }
 }
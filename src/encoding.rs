@@ -0,0 +1,101 @@
+use crate::ciphers::{
+    Base32Decode, Base32Encode, Base64Decode, Base64Encode, CipherError, Encryptor, HexDecode,
+    HexEncode,
+};
+
+/// Text representation of bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// Every byte is a code point below 256.
+    Latin1,
+    Hex,
+    Base32,
+    Base64,
+}
+
+impl Encoding {
+    pub const ALL: [Self; 5] = [
+        Self::Utf8,
+        Self::Latin1,
+        Self::Hex,
+        Self::Base32,
+        Self::Base64,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Latin1 => "Latin-1",
+            Self::Hex => "Hex",
+            Self::Base32 => "Base32",
+            Self::Base64 => "Base64",
+        }
+    }
+
+    /// File extension of encoded text.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Utf8 | Self::Latin1 => "txt",
+            Self::Hex => "hex",
+            Self::Base32 => "b32",
+            Self::Base64 => "b64",
+        }
+    }
+
+    /// Converts text into bytes.
+    ///
+    /// Whitespace is ignored for hex, Base32 and Base64.
+    pub fn decode(self, text: &str) -> Result<Vec<u8>, String> {
+        let r = match self {
+            Self::Utf8 => return Ok(text.as_bytes().to_vec()),
+            Self::Latin1 => {
+                return text
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        u8::try_from(c).map_err(|_| {
+                            format!("{:?} at offset {} is not a Latin-1 character", c, i)
+                        })
+                    })
+                    .collect()
+            }
+            Self::Hex => transform(HexDecode::new(), text.as_bytes()),
+            Self::Base32 => transform(Base32Decode::new(), text.as_bytes()),
+            Self::Base64 => transform(Base64Decode::new(), text.as_bytes()),
+        };
+        r.map_err(|e| e.to_string())
+    }
+
+    /// Converts bytes into text.
+    ///
+    /// Returns whether conversion is lossy, which only happens for invalid UTF-8.
+    pub fn encode(self, data: &[u8]) -> (String, bool) {
+        let r = match self {
+            Self::Utf8 => {
+                return match String::from_utf8_lossy(data) {
+                    v @ std::borrow::Cow::Borrowed(_) => (v.into_owned(), false),
+                    v => (v.into_owned(), true),
+                }
+            }
+            Self::Latin1 => return (String::from_iter(data.iter().map(|&b| b as char)), false),
+            Self::Hex => transform(HexEncode::new(), data),
+            Self::Base32 => transform(Base32Encode::new(), data),
+            Self::Base64 => transform(Base64Encode::new(), data),
+        };
+
+        // Encoders accept every byte and output ASCII
+        let v = r.unwrap();
+        (String::from_iter(v.into_iter().map(char::from)), false)
+    }
+}
+
+fn transform(mut e: impl Encryptor, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let mut ret = Vec::new();
+    for &b in data {
+        ret.extend_from_slice(e.encrypt_byte(b)?);
+    }
+    ret.extend(e.encrypt_finish()?);
+    Ok(ret)
+}
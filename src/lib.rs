@@ -0,0 +1,5 @@
+pub mod analysis;
+pub mod ciphers;
+pub mod encoding;
+pub mod parsers;
+pub mod util;
@@ -1,7 +1,4 @@
 mod app;
-mod ciphers;
-mod parsers;
-mod util;
 
 use app::App;
 
@@ -29,6 +29,20 @@ impl<T: fmt::Display> fmt::Display for CoprimeError<T> {
     }
 }
 
+/// Multiplicative inverse of `value` modulo `modulo`.
+pub fn inverse_mod(value: u32, modulo: u32) -> Result<u32, CoprimeError<u32>> {
+    let r = (value as i64).extended_gcd(&(modulo as i64));
+    if r.gcd != 1 {
+        Err(CoprimeError {
+            value,
+            modulo,
+            gcd: r.gcd as _,
+        })
+    } else {
+        Ok(r.x.rem_euclid(modulo as _) as _)
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
 pub struct ModuloU8<const M: u8>(u8);
@@ -120,102 +134,450 @@ impl<const M: u8> MatrixU8<M> {
         self.size
     }
 
+    /// Elements in row-major order.
+    #[inline]
+    pub fn as_slice(&self) -> &[ModuloU8<M>] {
+        &self.arr
+    }
+
+    pub fn identity(size: usize) -> Self {
+        Self::from_zn(&MatrixZn::identity(size, M as _))
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_zn(&self.to_zn().transpose())
+    }
+
+    pub fn determinant(&self) -> ModuloU8<M> {
+        ModuloU8(u32::from(self.to_zn().determinant()) as _)
+    }
+
+    /// See [`MatrixZn::rank`].
+    pub fn rank(&self) -> usize {
+        self.to_zn().rank()
+    }
+
+    pub fn adjugate(&self) -> Self {
+        Self::from_zn(&self.to_zn().adjugate())
+    }
+
+    /// See [`MatrixZn::inverse`].
     pub fn inverse(&self) -> Result<Self, MatrixInversionError<u8>> {
-        let Self { size: n, arr } = self;
-        let n = *n;
+        match self.to_zn().inverse() {
+            Ok(v) => Ok(Self::from_zn(&v)),
+            Err(MatrixInversionError::SingularError {
+                determinant,
+                modulus,
+                gcd,
+            }) => Err(MatrixInversionError::SingularError {
+                determinant: determinant as _,
+                modulus: modulus as _,
+                gcd: gcd as _,
+            }),
+        }
+    }
+
+    fn to_zn(&self) -> MatrixZn {
+        MatrixZn::new(self.size, M as _, self.arr.iter().map(|&v| v.0 as _))
+    }
+
+    fn from_zn(mat: &MatrixZn) -> Self {
+        Self {
+            size: mat.size,
+            arr: mat
+                .arr
+                .iter()
+                .map(|&v| ModuloU8(u32::from(v) as _))
+                .collect(),
+        }
+    }
+
+    pub fn slice_mult(&self, in_: &[ModuloU8<M>], out: &mut [ModuloU8<M>]) {
+        for (p, row) in out.iter_mut().zip(self.arr.chunks_exact(self.size)) {
+            *p = 0.into();
+            for (&a, &b) in in_.iter().zip(row) {
+                *p += a * b;
+            }
+        }
+    }
+}
+
+impl<const M: u8> Mul for &MatrixU8<M> {
+    type Output = MatrixU8<M>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        MatrixU8::from_zn(&(&self.to_zn() * &rhs.to_zn()))
+    }
+}
+
+/// Integer modulo a runtime modulus.
+///
+/// Operands of arithmetic must have the same modulus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Zn {
+    value: u32,
+    modulus: u32,
+}
+
+impl Zn {
+    #[inline]
+    pub fn new(value: u32, modulus: u32) -> Self {
+        assert!(modulus > 0, "modulus must be positive");
+        Self {
+            value: value % modulus,
+            modulus,
+        }
+    }
+
+    #[inline]
+    pub fn value(self) -> u32 {
+        self.value
+    }
+
+    #[inline]
+    pub fn modulus(self) -> u32 {
+        self.modulus
+    }
+
+    pub fn inverse(self) -> Result<Self, CoprimeError<u32>> {
+        Ok(Self {
+            value: inverse_mod(self.value, self.modulus)?,
+            modulus: self.modulus,
+        })
+    }
+
+    #[inline]
+    fn with(self, rhs: Self, f: impl FnOnce(u64, u64) -> u64) -> Self {
+        debug_assert_eq!(self.modulus, rhs.modulus, "modulus mismatch");
+        let m = self.modulus as u64;
+        Self {
+            value: (f(self.value as u64, rhs.value as u64) % m) as _,
+            modulus: self.modulus,
+        }
+    }
+}
+
+impl From<Zn> for u32 {
+    fn from(value: Zn) -> Self {
+        value.value
+    }
+}
+
+impl Add for Zn {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.with(rhs, |a, b| a + b)
+    }
+}
+
+impl AddAssign for Zn {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Zn {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let m = self.modulus as u64;
+        self.with(rhs, |a, b| a + m - b)
+    }
+}
+
+impl SubAssign for Zn {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul for Zn {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.with(rhs, |a, b| a * b)
+    }
+}
+
+impl MulAssign for Zn {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+/// Square matrix over [`Zn`] with a runtime modulus.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MatrixZn {
+    size: usize,
+    modulus: u32,
+    arr: Vec<Zn>,
+}
+
+impl MatrixZn {
+    /// Elements are given in row-major order.
+    pub fn new(size: usize, modulus: u32, arr: impl IntoIterator<Item = u32>) -> Self {
+        let arr: Vec<_> = arr.into_iter().map(|v| Zn::new(v, modulus)).collect();
+        assert_eq!(size * size, arr.len());
+        Self { size, modulus, arr }
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    pub fn modulus(&self) -> u32 {
+        self.modulus
+    }
+
+    /// Elements in row-major order.
+    #[inline]
+    pub fn as_slice(&self) -> &[Zn] {
+        &self.arr
+    }
 
-        let mut a = arr.clone();
-        let mut p: Vec<_> = (0..n).collect();
+    /// Inverse by Gauss-Jordan elimination with unit pivots.
+    ///
+    /// Columns without a unit pivot can still be invertible for composite moduli,
+    /// those fall back to `adj(A) / det(A)`.
+    pub fn inverse(&self) -> Result<Self, MatrixInversionError<u32>> {
+        let n = self.size;
+        let zero = Zn::new(0, self.modulus);
+
+        let mut a = self.arr.clone();
+        let mut ia: Vec<_> = (0..n * n)
+            .map(|i| Zn::new((i / n == i % n) as _, self.modulus))
+            .collect();
 
         for i in 0..n {
-            let mut max_a = 0.into();
-            let mut imax = i;
+            let Some((k, t)) = (i..n).find_map(|k| Some((k, a[k * n + i].inverse().ok()?))) else {
+                return self.adjugate_inverse();
+            };
 
-            for k in 0..n {
-                let temp = a[k * n + i];
-                if temp > max_a {
-                    (max_a, imax) = (temp, k);
+            if k != i {
+                for j in 0..n {
+                    a.swap(i * n + j, k * n + j);
+                    ia.swap(i * n + j, k * n + j);
                 }
             }
-
-            if u8::from(max_a) == 0 {
-                return Err(MatrixInversionError::DegenerateError);
+            for j in 0..n {
+                a[i * n + j] *= t;
+                ia[i * n + j] *= t;
             }
 
-            if imax != i {
-                p.swap(i, imax);
-                let i1 = i * n;
-                let i2 = imax * n;
+            for r in (0..n).filter(|&r| r != i) {
+                let f = a[r * n + i];
+                if f == zero {
+                    continue;
+                }
                 for j in 0..n {
-                    a.swap(i1 + j, i2 + j);
+                    let (x, y) = (f * a[i * n + j], f * ia[i * n + j]);
+                    a[r * n + j] -= x;
+                    ia[r * n + j] -= y;
                 }
             }
+        }
 
-            for j in i + 1..n {
-                let mut t = a[i * n + i].inverse()?;
-                a[j * n + i] *= t;
+        Ok(Self {
+            size: n,
+            modulus: self.modulus,
+            arr: ia,
+        })
+    }
 
-                for k in i + 1..n {
-                    t = a[j * n + i] * a[i * n + k];
-                    a[j * n + k] -= t;
-                }
-            }
+    fn adjugate_inverse(&self) -> Result<Self, MatrixInversionError<u32>> {
+        let det = self.determinant();
+        let det_inv = det
+            .inverse()
+            .map_err(|e| MatrixInversionError::SingularError {
+                determinant: e.value,
+                modulus: e.modulo,
+                gcd: e.gcd,
+            })?;
+
+        let mut ret = self.adjugate();
+        for v in ret.arr.iter_mut() {
+            *v *= det_inv;
         }
+        Ok(ret)
+    }
 
-        let mut ia = vec![<ModuloU8<M>>::from(0); n * n];
+    /// Identity matrix.
+    pub fn identity(size: usize, modulus: u32) -> Self {
+        Self::new(
+            size,
+            modulus,
+            (0..size * size).map(|i| (i / size == i % size) as _),
+        )
+    }
 
-        for j in 0..n {
-            for i in 0..n {
-                ia[i * n + j] = <_>::from((p[i] == j) as u8);
+    pub fn transpose(&self) -> Self {
+        let n = self.size;
+        Self::new(
+            n,
+            self.modulus,
+            (0..n * n).map(|i| self.arr[(i % n) * n + i / n].into()),
+        )
+    }
 
-                for k in 0..i {
-                    let t = a[i * n + k] * ia[k * n + j];
-                    ia[i * n + j] -= t;
-                }
-            }
+    pub fn determinant(&self) -> Zn {
+        let n = self.size;
+        let mut a: Vec<u32> = self.arr.iter().map(|&v| v.into()).collect();
+        let (rank, negated) = echelon(n, self.modulus, &mut a);
+        if rank < n {
+            return Zn::new(0, self.modulus);
+        }
 
-            for i in (0..n).rev() {
-                for k in i + 1..n {
-                    let t = a[i * n + k] * ia[k * n + j];
-                    ia[i * n + j] -= t;
-                }
+        let mut det = Zn::new(1, self.modulus);
+        for i in 0..n {
+            det *= Zn::new(a[i * n + i], self.modulus);
+        }
+        if negated {
+            det = Zn::new(0, self.modulus) - det;
+        }
+        det
+    }
+
+    /// Number of nonzero rows in row echelon form.
+    ///
+    /// Equals the usual rank for prime moduli.
+    pub fn rank(&self) -> usize {
+        let mut a: Vec<u32> = self.arr.iter().map(|&v| v.into()).collect();
+        echelon(self.size, self.modulus, &mut a).0
+    }
+
+    /// Transposed cofactor matrix.
+    pub fn adjugate(&self) -> Self {
+        let n = self.size;
+        if n == 1 {
+            return Self::new(1, self.modulus, [1]);
+        }
 
-                ia[i * n + j] *= a[i * n + i].inverse()?;
+        let mut arr = Vec::with_capacity(n * n);
+        for i in 0..n {
+            for j in 0..n {
+                // Minor without row j and column i, already transposed
+                let minor = Self::new(
+                    n - 1,
+                    self.modulus,
+                    (0..n * n)
+                        .filter(|&k| (k / n != j) && (k % n != i))
+                        .map(|k| self.arr[k].into()),
+                );
+                let v = minor.determinant();
+                arr.push(if (i + j) % 2 == 0 {
+                    v
+                } else {
+                    Zn::new(0, self.modulus) - v
+                });
             }
         }
 
-        Ok(Self { arr: ia, size: n })
+        Self {
+            size: n,
+            modulus: self.modulus,
+            arr,
+        }
     }
 
-    pub fn slice_mult(&self, in_: &[ModuloU8<M>], out: &mut [ModuloU8<M>]) {
-        for i in 0..self.size {
-            let p = &mut out[i];
-            *p = 0.into();
-            let i_ = i * self.size;
-
-            for j in 0..self.size {
-                *p += in_[j] * self.arr[i_ + j];
+    pub fn slice_mult(&self, in_: &[Zn], out: &mut [Zn]) {
+        for (p, row) in out.iter_mut().zip(self.arr.chunks_exact(self.size)) {
+            *p = Zn::new(0, self.modulus);
+            for (&a, &b) in in_.iter().zip(row) {
+                *p += a * b;
             }
         }
     }
 }
 
-pub enum MatrixInversionError<T> {
-    CoprimeError(CoprimeError<T>),
-    DegenerateError,
+impl Mul for &MatrixZn {
+    type Output = MatrixZn;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        assert_eq!(self.size, rhs.size);
+        let n = self.size;
+        let mut arr = vec![Zn::new(0, self.modulus); n * n];
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    arr[i * n + j] += self.arr[i * n + k] * rhs.arr[k * n + j];
+                }
+            }
+        }
+
+        MatrixZn {
+            size: n,
+            modulus: self.modulus,
+            arr,
+        }
+    }
 }
 
-impl<T> From<CoprimeError<T>> for MatrixInversionError<T> {
-    fn from(value: CoprimeError<T>) -> Self {
-        Self::CoprimeError(value)
+/// Reduces a row-major matrix into row echelon form modulo `modulus`.
+///
+/// Uses Euclidean row reduction, which only needs unimodular integer row operations
+/// and works for composite moduli without overflow.
+/// Returns the number of nonzero rows and whether row swaps negated the determinant.
+fn echelon(n: usize, modulus: u32, a: &mut [u32]) -> (usize, bool) {
+    let m = modulus as u64;
+    let (mut r, mut negated) = (0, false);
+
+    for c in 0..n {
+        if r == n {
+            break;
+        }
+
+        // Smallest nonzero entry in the column becomes the pivot
+        while let Some(k) = (r..n)
+            .filter(|&k| a[k * n + c] != 0)
+            .min_by_key(|&k| a[k * n + c])
+        {
+            if k != r {
+                for j in 0..n {
+                    a.swap(r * n + j, k * n + j);
+                }
+                negated = !negated;
+            }
+
+            let p = a[r * n + c] as u64;
+            let mut done = true;
+            for i in r + 1..n {
+                let q = a[i * n + c] as u64 / p;
+                for j in c..n {
+                    let t = (q * a[r * n + j] as u64) % m;
+                    a[i * n + j] = ((a[i * n + j] as u64 + m - t) % m) as _;
+                }
+                done &= a[i * n + c] == 0;
+            }
+            if done {
+                r += 1;
+                break;
+            }
+        }
     }
+
+    (r, negated)
+}
+
+pub enum MatrixInversionError<T> {
+    /// Determinant is not a unit.
+    SingularError { determinant: T, modulus: T, gcd: T },
 }
 
 impl<T: fmt::Debug> fmt::Debug for MatrixInversionError<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::CoprimeError(e) => e.fmt(f),
-            Self::DegenerateError => write!(f, "Degenerate matrix"),
+            Self::SingularError {
+                determinant,
+                modulus,
+                gcd,
+            } => write!(
+                f,
+                "Singular matrix, determinant {:?} is not coprime to {:?} (GCD: {:?})",
+                determinant, modulus, gcd
+            ),
         }
     }
 }
@@ -223,8 +585,174 @@ impl<T: fmt::Debug> fmt::Debug for MatrixInversionError<T> {
 impl<T: fmt::Display> fmt::Display for MatrixInversionError<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::CoprimeError(e) => e.fmt(f),
-            Self::DegenerateError => write!(f, "Degenerate matrix"),
+            Self::SingularError {
+                determinant,
+                modulus,
+                gcd,
+            } => write!(
+                f,
+                "Singular matrix, determinant {} is not coprime to {} (GCD: {})",
+                determinant, modulus, gcd
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Xorshift64 generator, tests need no external crate.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u32) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as _
+        }
+
+        fn unit(&mut self, modulus: u32) -> u32 {
+            loop {
+                let v = self.below(modulus);
+                if inverse_mod(v, modulus).is_ok() {
+                    return v;
+                }
+            }
+        }
+
+        /// Invertible matrix as permuted `L * U`, with unit diagonal in both factors.
+        fn invertible(&mut self, n: usize, modulus: u32) -> MatrixZn {
+            let lower = MatrixZn::new(
+                n,
+                modulus,
+                (0..n * n)
+                    .map(|i| match (i / n, i % n) {
+                        (r, c) if r == c => 1,
+                        (r, c) if r > c => self.below(modulus),
+                        _ => 0,
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            let upper = MatrixZn::new(
+                n,
+                modulus,
+                (0..n * n)
+                    .map(|i| match (i / n, i % n) {
+                        (r, c) if r == c => self.unit(modulus),
+                        (r, c) if r < c => self.below(modulus),
+                        _ => 0,
+                    })
+                    .collect::<Vec<_>>(),
+            );
+
+            let mut ret = &lower * &upper;
+            for i in (1..n).rev() {
+                let k = self.below(i as u32 + 1) as usize;
+                for j in 0..n {
+                    ret.arr.swap(i * n + j, k * n + j);
+                }
+            }
+            ret
+        }
+    }
+
+    fn inverse_of(size: usize, modulus: u32, arr: &[u32]) -> Vec<u32> {
+        let inv = MatrixZn::new(size, modulus, arr.iter().copied())
+            .inverse()
+            .unwrap();
+        inv.as_slice().iter().map(|&v| v.into()).collect()
+    }
+
+    #[test]
+    fn inverse_of_random_invertible() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+
+        // Composite, prime and prime power moduli
+        for modulus in [26, 36, 29, 27] {
+            for n in 1..=5 {
+                for _ in 0..50 {
+                    let a = rng.invertible(n, modulus);
+                    let inv = a.inverse().unwrap();
+                    let id = MatrixZn::identity(n, modulus);
+
+                    assert_eq!(&a * &inv, id, "{:?}", a);
+                    assert_eq!(&inv * &a, id, "{:?}", a);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_of_random_matrix() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+
+        for modulus in [26, 36, 29, 27] {
+            for n in 1..=4 {
+                for _ in 0..50 {
+                    let a = MatrixZn::new(
+                        n,
+                        modulus,
+                        (0..n * n).map(|_| rng.below(modulus)).collect::<Vec<_>>(),
+                    );
+                    let det = u32::from(a.determinant());
+
+                    match a.inverse() {
+                        Ok(inv) => {
+                            assert!(inverse_mod(det, modulus).is_ok(), "{:?}", a);
+                            assert_eq!(&a * &inv, MatrixZn::identity(n, modulus), "{:?}", a);
+                        }
+                        Err(MatrixInversionError::SingularError {
+                            determinant, gcd, ..
+                        }) => {
+                            assert_eq!(determinant, det, "{:?}", a);
+                            assert_ne!(gcd, 1, "{:?}", a);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_with_small_unit_pivot() {
+        // 3 is the only unit in the first column, 20 and 24 are larger
+        assert_eq!(inverse_of(2, 26, &[20, 1, 3, 1]), [23, 3, 9, 18]);
+        assert_eq!(
+            inverse_of(3, 26, &[13, 2, 1, 3, 0, 0, 24, 5, 1]),
+            [0, 9, 0, 17, 19, 9, 19, 1, 8]
+        );
+    }
+
+    #[test]
+    fn inverse_without_unit_pivot() {
+        // No unit in the first column, falls back to the adjugate
+        assert_eq!(
+            inverse_of(3, 26, &[6, 24, 1, 13, 16, 10, 20, 17, 15]),
+            [8, 5, 10, 21, 8, 21, 21, 12, 8]
+        );
+    }
+
+    #[test]
+    fn inverse_of_singular() {
+        // Second one has no unit pivot in the first column
+        for (arr, det) in [([2, 4, 1, 3], 2), ([2, 1, 4, 1], 24)] {
+            let err = MatrixZn::new(2, 26, arr).inverse().unwrap_err();
+            let MatrixInversionError::SingularError {
+                determinant,
+                modulus,
+                gcd,
+            } = err;
+            assert_eq!((determinant, modulus, gcd), (det, 26, 2));
+        }
+
+        let err = MatrixU8::<26>::new(2, [13, 0, 0, 1].map(ModuloU8::from).to_vec())
+            .inverse()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Singular matrix, determinant 13 is not coprime to 26 (GCD: 13)"
+        );
+    }
+}